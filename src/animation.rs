@@ -1,51 +1,95 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use anyhow::{Context, Result, bail};
+use bevy::prelude::*;
 
-use crate::points::PointsMaterial;
+use crate::{
+    error_handler,
+    keymap::{RegisterCommand, command_just_pressed},
+    points::PointsMaterial,
+    project::ProjectSettings,
+};
 
 pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Animation::default())
+        .register_command("play", "Play or pause the animation", &["Space"])
+        .add_systems(
+            Update,
+            (
+                apply_settings
+                    .pipe(error_handler)
+                    .run_if(resource_changed::<ProjectSettings>),
+                animate.run_if(animating),
+                toggle_animation.run_if(command_just_pressed("play")),
+            ),
+        );
+}
+
+pub(super) fn player_plugin(app: &mut App) {
     app.insert_resource(Animation::default()).add_systems(
         Update,
         (
-            animate.run_if(animating),
-            toggle_animation.run_if(input_just_pressed(KeyCode::Space)),
+            apply_settings
+                .pipe(error_handler)
+                .run_if(resource_changed::<ProjectSettings>),
+            animate,
         ),
     );
 }
 
-pub(super) fn player_plugin(app: &mut App) {
-    app.insert_resource(Animation::default())
-        .add_systems(Update, animate);
+/// Default seconds to animate from source to target, the animation then plays back to source
+pub(crate) const DURATION: f32 = 2.5;
+/// Default easing from source to target
+pub(crate) const EASING: EaseFunction = EaseFunction::CubicInOut;
+
+pub(crate) type AnimationCurve = PingPongCurve<f32, LinearReparamCurve<f32, EasingCurve<f32>>>;
+
+/// Interpolation over time, easing from source (0) to target (1) over `duration` seconds and back again
+pub(crate) fn curve(duration: f32, easing: EaseFunction) -> Result<AnimationCurve> {
+    if !(duration > 0.0 && duration.is_finite()) {
+        bail!("duration {} is not positive", duration);
+    }
+    let invalid = || format!("Invalid animation duration {}", duration);
+    let domain = interval(0.0, duration).ok().with_context(invalid)?;
+    EasingCurve::new(0.0, 1.0, easing)
+        .reparametrize_linear(domain)
+        .ok()
+        .with_context(invalid)?
+        .ping_pong()
+        .ok()
+        .with_context(invalid)
 }
 
 #[derive(Resource)]
 struct Animation {
     animating: bool,
-    curve: PingPongCurve<f32, LinearReparamCurve<f32, EasingCurve<f32>>>,
+    curve: AnimationCurve,
     time: f32,
 }
 
 impl Animation {
-    fn new(easing: EaseFunction) -> Self {
+    fn new(curve: AnimationCurve) -> Self {
         Self {
             animating: false,
-            curve: EasingCurve::new(0.0, 1.0, easing)
-                .reparametrize_linear(interval(0.0, 2.5).unwrap())
-                .expect("good curve")
-                .ping_pong()
-                .expect("good curve"),
+            curve,
             time: 0.0,
         }
     }
 }
 impl Default for Animation {
     fn default() -> Self {
-        Self::new(EaseFunction::CubicInOut)
+        Self::new(curve(DURATION, EASING).expect("valid default duration"))
     }
 }
 
 #[derive(Component)]
 pub struct Animatable;
 
+// Restart the animation with the loaded project's timing
+fn apply_settings(settings: Res<ProjectSettings>, mut animation: ResMut<Animation>) -> Result<()> {
+    animation.curve = curve(settings.duration, settings.easing)?;
+    animation.time = 0.0;
+    Ok(())
+}
+
 fn animating(animation: Res<Animation>) -> bool {
     animation.animating
 }
@@ -92,7 +136,7 @@ mod tests {
 
     #[test]
     fn test_animation_new() {
-        let anim = Animation::new(EaseFunction::Linear);
+        let anim = Animation::new(curve(1.0, EaseFunction::Linear).unwrap());
         assert!(!anim.animating);
         assert_eq!(anim.time, 0.0);
     }
@@ -104,6 +148,21 @@ mod tests {
         assert_eq!(anim.time, 0.0);
     }
 
+    #[test]
+    fn test_curve() {
+        let curve = curve(2.0, EaseFunction::Linear).unwrap();
+        assert_eq!(curve.domain().length(), 4.0);
+        assert_eq!(curve.sample(0.0), Some(0.0));
+        assert_eq!(curve.sample(1.0), Some(0.5));
+        assert_eq!(curve.sample(2.0), Some(1.0));
+        assert_eq!(curve.sample(3.0), Some(0.5));
+        assert_eq!(curve.sample(4.0), Some(0.0));
+
+        for duration in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(super::curve(duration, EaseFunction::Linear).is_err());
+        }
+    }
+
     #[test]
     fn test_toggle_animation() {
         let mut app = App::new();
@@ -141,4 +200,24 @@ mod tests {
         let animation = app.world().resource::<Animation>();
         assert!(animation.time > 0.0);
     }
+
+    #[test]
+    fn test_apply_settings() {
+        let mut app = App::new();
+        app.init_resource::<Animation>()
+            .insert_resource(ProjectSettings {
+                duration: 1.0,
+                ..default()
+            })
+            .add_systems(
+                Update,
+                apply_settings
+                    .pipe(error_handler)
+                    .run_if(resource_changed::<ProjectSettings>),
+            );
+        app.update();
+
+        let animation = app.world().resource::<Animation>();
+        assert_eq!(animation.curve.domain().length(), 2.0);
+    }
 }
@@ -2,16 +2,23 @@ use bevy::{
     DefaultPlugins,
     app::App,
     prelude::*,
+    window::PrimaryWindow,
     winit::{WakeUp, WinitPlugin},
 };
 
 #[cfg(target_arch = "wasm32")]
 use crate::webgpu;
 use crate::{
-    AppState, animation, camera, cli, draw, points,
+    AppState, animation, camera, cli,
+    config::Config,
+    draw,
+    keymap::{self, Keymap},
+    points,
     project::{self, LoadProjectData},
     ui,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{autosave, drop, files};
 
 pub enum AppPlugin {
     Editor(cli::Args),
@@ -30,6 +37,9 @@ impl AppPlugin {
     }
 }
 
+const EDITOR_TITLE: &str = "Terp";
+const PLAYER_TITLE: &str = "Terp Player";
+
 fn title_suffix(title: &str, args: &cli::Args) -> String {
     if let Some(project) = args.project() {
         format!("{} - {}", title, project.display())
@@ -40,9 +50,16 @@ fn title_suffix(title: &str, args: &cli::Args) -> String {
 
 impl Plugin for AppPlugin {
     fn build(&self, app: &mut App) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let config = Config::load().unwrap_or_else(|err| {
+            eprintln!("Using the default config: {:?}", err);
+            default()
+        });
+        #[cfg(target_arch = "wasm32")]
+        let config = Config::default();
         let (title, rez) = match self {
-            AppPlugin::Editor(args) => (title_suffix("Terp", args), (1200., 600.)),
-            AppPlugin::Player(args) => (title_suffix("Terp Player", args), (600., 600.)),
+            AppPlugin::Editor(args) => (title_suffix(EDITOR_TITLE, args), config.window.editor),
+            AppPlugin::Player(args) => (title_suffix(PLAYER_TITLE, args), config.window.player),
         };
         let default_plugins = DefaultPlugins
             .set(WindowPlugin {
@@ -57,6 +74,12 @@ impl Plugin for AppPlugin {
             .disable::<WinitPlugin<WakeUp>>()
             .add(WinitPlugin::<LoadProjectData>::default());
 
+        // Plugins keep the configured resources
+        app.insert_resource(config.brush())
+            .insert_resource(config.project_settings())
+            .insert_resource(Keymap::new(config.keys.clone()))
+            .insert_resource(config);
+
         match self {
             AppPlugin::Editor(args) => {
                 app.add_plugins((
@@ -67,11 +90,18 @@ impl Plugin for AppPlugin {
                     animation::plugin,
                     ui::plugin,
                     project::plugin,
+                    keymap::plugin,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    (autosave::plugin, drop::plugin, files::plugin),
                     #[cfg(target_arch = "wasm32")]
                     webgpu::plugin,
                 ))
                 .insert_state(AppState::Idle)
-                .insert_resource(args.clone());
+                .insert_resource(args.clone())
+                .add_systems(
+                    Update,
+                    update_title(EDITOR_TITLE).run_if(resource_changed::<cli::Args>),
+                );
             }
             AppPlugin::Player(args) => {
                 app.add_plugins((
@@ -81,16 +111,33 @@ impl Plugin for AppPlugin {
                     draw::player_plugin,
                     animation::player_plugin,
                     project::player_plugin,
+                    #[cfg(not(target_arch = "wasm32"))]
+                    drop::player_plugin,
                     #[cfg(target_arch = "wasm32")]
                     webgpu::plugin,
                 ))
                 .insert_state(AppState::Idle)
-                .insert_resource(args.clone());
+                .insert_resource(args.clone())
+                .add_systems(
+                    Update,
+                    update_title(PLAYER_TITLE).run_if(resource_changed::<cli::Args>),
+                );
             }
         }
     }
 }
 
+// The project can be changed after launch
+fn update_title(
+    title: &'static str,
+) -> impl FnMut(Res<cli::Args>, Query<&mut Window, With<PrimaryWindow>>) {
+    move |args: Res<cli::Args>, mut windows: Query<&mut Window, With<PrimaryWindow>>| {
+        for mut window in &mut windows {
+            window.title = title_suffix(title, &args);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,526 @@
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    path::{self, Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use bevy::{prelude::*, time::common_conditions::on_timer};
+
+use crate::{
+    cli,
+    config::Config,
+    error_handler,
+    project::{
+        CurrentProject, Format, LoadProject, LoadProjectData, Project, ProjectSaved,
+        SaveProjectData,
+    },
+};
+
+/// How often the editor saves a recovery copy of the project
+const INTERVAL: Duration = Duration::from_secs(30);
+
+const PROMPT_COLOR: Color = Color::Srgba(Srgba::rgb(0.2, 0.2, 0.2));
+const CHOICE_COLOR: Color = Color::Srgba(Srgba::rgb(0.4, 0.4, 0.4));
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Config>()
+        .add_systems(Startup, check_recovery.pipe(error_handler))
+        .add_systems(
+            Update,
+            (
+                follow_project.run_if(resource_changed::<cli::Args>),
+                track_saved.pipe(error_handler),
+                autosave.pipe(error_handler).run_if(on_timer(INTERVAL)),
+            )
+                .chain(),
+        );
+}
+
+/// Recovery copy of the project being edited
+#[derive(Resource)]
+pub(crate) struct Recovery {
+    path: PathBuf,
+    /// Recovered project the user has not yet restored or discarded, autosave waits for them
+    pending: Option<Vec<u8>>,
+    /// Last project autosave saw, so unchanged projects are not rewritten
+    last: Option<Vec<u8>>,
+    /// Project as last loaded or saved by the user, unknown if it couldn't be read
+    saved: Option<Vec<u8>>,
+    /// The next project loaded is the restored one, it is not saved
+    restoring: bool,
+}
+
+#[derive(Component)]
+struct RestorePrompt;
+
+/// Longest part of a project's file name kept in its recovery file name
+const NAME_LEN: usize = 64;
+
+/// Recovery file for a project, unnamed sessions share one.
+/// Named projects are recovered by a hash of their canonical path, which fits in a file name
+/// however deep the project is. The start of the project's name is kept so the file can be recognized.
+fn recovery_path(project: Option<&Path>) -> PathBuf {
+    let directory = dirs::data_local_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(clap::crate_name!())
+        .join("recovery");
+    // A project that hasn't been saved yet doesn't exist, so canonicalize its directory.
+    // It is recovered in the same file before and after it is first saved.
+    let project = project.and_then(|project| {
+        let absolute = path::absolute(project).ok()?;
+        let canonical = absolute
+            .parent()
+            .and_then(|directory| fs::canonicalize(directory).ok())
+            .zip(absolute.file_name())
+            .map(|(directory, name)| directory.join(name));
+        Some(canonical.unwrap_or(absolute))
+    });
+    let name = match project {
+        Some(project) => {
+            let name: String = project
+                .file_name()
+                .map(OsStr::to_string_lossy)
+                .unwrap_or_default()
+                .chars()
+                .take(NAME_LEN)
+                .collect();
+            format!(
+                "{}-{:016x}.terp",
+                name,
+                fnv1a(project.as_os_str().as_encoded_bytes())
+            )
+        }
+        None => "untitled.terp".into(),
+    };
+    directory.join(name)
+}
+
+// Stable across releases, unlike the standard library's hashers
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Encode projects the same way whatever format they were saved in, so they can be compared
+fn canonical(project: &Project) -> Result<Vec<u8>> {
+    let mut project = project.clone();
+    project.drawings.sort_by(|a, b| a.layer.total_cmp(&b.layer));
+    project.to_bytes(Format::Binary)
+}
+
+// The project the editor starts with
+fn saved_project(args: &cli::Args, config: &Config) -> Result<Vec<u8>> {
+    match args.project() {
+        Some(path) if path.exists() => canonical(&Project::load(path)?),
+        _ => canonical(&config.new_project()),
+    }
+}
+
+fn check_recovery(args: Res<cli::Args>, config: Res<Config>, mut commands: Commands) -> Result<()> {
+    let mut recovery = Recovery {
+        path: recovery_path(args.project()),
+        pending: None,
+        last: None,
+        saved: None,
+        restoring: false,
+    };
+    // Autosave needs the resource whatever state the last session's recovery file is in
+    let result = recover(&mut recovery, &args, &config, &mut commands);
+    commands.insert_resource(recovery);
+    result
+}
+
+fn recover(
+    recovery: &mut Recovery,
+    args: &cli::Args,
+    config: &Config,
+    commands: &mut Commands,
+) -> Result<()> {
+    // Read once at startup, then kept up to date as projects are loaded and saved
+    let saved = saved_project(args, config);
+    recovery.saved = saved.as_ref().ok().cloned();
+    if !recovery.path.exists() {
+        if let Some(directory) = recovery.path.parent() {
+            fs::create_dir_all(directory)
+                .with_context(|| format!("Failed to create {}", directory.display()))?;
+        }
+        return saved.map(drop);
+    }
+    let recovered = match Project::load(&recovery.path).and_then(|project| canonical(&project)) {
+        Ok(recovered) => recovered,
+        Err(err) => {
+            // A crash while writing can leave a truncated file, keep it out of the way
+            let corrupt = corrupt_path(&recovery.path);
+            warn!(
+                "Moving unreadable recovery file to {}: {:?}",
+                corrupt.display(),
+                err
+            );
+            fs::rename(&recovery.path, &corrupt)
+                .with_context(|| format!("Failed to move {}", recovery.path.display()))?;
+            return saved.map(drop);
+        }
+    };
+    if recovery.saved.as_ref() == Some(&recovered) {
+        fs::remove_file(&recovery.path)
+            .with_context(|| format!("Failed to remove {}", recovery.path.display()))?;
+    } else {
+        recovery.pending = Some(recovered);
+        spawn_prompt(commands);
+    }
+    saved.map(drop)
+}
+
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+// Recover a project opened or saved under a new name in its own file.
+// The previous project's recovery file is kept, it is offered again when that project is opened.
+fn follow_project(args: Res<cli::Args>, mut recovery: ResMut<Recovery>) {
+    let path = recovery_path(args.project());
+    if path != recovery.path {
+        recovery.path = path;
+        recovery.last = None;
+    }
+}
+
+// Remember the project the user last loaded or saved, so autosave doesn't have to read it back.
+// Saves only count once written, a failed save must not lose the recovery file.
+fn track_saved(
+    args: Res<cli::Args>,
+    mut recovery: ResMut<Recovery>,
+    mut loads: EventReader<LoadProject>,
+    mut saves: EventReader<ProjectSaved>,
+) -> Result<()> {
+    for LoadProject(project) in loads.read() {
+        if recovery.restoring {
+            recovery.restoring = false;
+        } else {
+            recovery.saved = Some(canonical(project)?);
+        }
+    }
+    for ProjectSaved(save) in saves.read() {
+        // Autosave's own recovery saves are not the user's
+        if args.project() == Some(save.path.as_path()) {
+            recovery.saved = Some(canonical(&Project::from_bytes(&save.data)?)?);
+        }
+    }
+    Ok(())
+}
+
+/// Whether the project being edited differs from the one last loaded or saved
+pub(crate) fn unsaved_changes(recovery: Res<Recovery>, current: CurrentProject) -> bool {
+    recovery.saved.as_ref() != canonical(&current.project()).ok().as_ref()
+}
+
+fn autosave(
+    mut recovery: ResMut<Recovery>,
+    current: CurrentProject,
+    mut commands: Commands,
+) -> Result<()> {
+    if recovery.pending.is_some() {
+        return Ok(());
+    }
+    let data = canonical(&current.project())?;
+    if recovery.last.as_ref() == Some(&data) {
+        return Ok(());
+    }
+    if recovery.saved.as_ref() == Some(&data) {
+        // Saved by the user, nothing to recover
+        if recovery.path.exists() {
+            fs::remove_file(&recovery.path)?;
+        }
+    } else {
+        commands.send_event(SaveProjectData {
+            path: recovery.path.clone(),
+            data: data.clone(),
+            // The recovery file is only ever the latest changes
+            backups: 0,
+        });
+    }
+    recovery.last = Some(data);
+    Ok(())
+}
+
+fn spawn_prompt(commands: &mut Commands) {
+    commands
+        .spawn((
+            RestorePrompt,
+            Node {
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            // Cover the editor so it can't be drawn in until the user chooses
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+            GlobalZIndex(1),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(20.0),
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(PROMPT_COLOR),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(Text::new("Restore unsaved changes from the last session?"));
+                    parent
+                        .spawn(Node {
+                            display: Display::Flex,
+                            column_gap: Val::Px(20.0),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            spawn_choice(parent, "Restore").observe(restore);
+                            spawn_choice(parent, "Discard").observe(discard);
+                        });
+                });
+        });
+}
+
+fn spawn_choice<'a>(parent: &'a mut ChildBuilder<'_>, label: &str) -> EntityCommands<'a> {
+    let mut button = parent.spawn((
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(CHOICE_COLOR),
+    ));
+    button.with_child(Text::new(label));
+    button
+}
+
+fn restore(
+    mut trigger: Trigger<Pointer<Click>>,
+    mut recovery: ResMut<Recovery>,
+    prompts: Query<Entity, With<RestorePrompt>>,
+    mut commands: Commands,
+) {
+    trigger.propagate(false);
+    if let Some(data) = recovery.pending.take() {
+        recovery.restoring = true;
+        commands.send_event(LoadProjectData(data));
+    }
+    for prompt in &prompts {
+        commands.entity(prompt).despawn_recursive();
+    }
+}
+
+fn discard(
+    mut trigger: Trigger<Pointer<Click>>,
+    mut recovery: ResMut<Recovery>,
+    prompts: Query<Entity, With<RestorePrompt>>,
+    mut commands: Commands,
+) {
+    trigger.propagate(false);
+    recovery.pending = None;
+    error_handler(In(fs::remove_file(&recovery.path).with_context(|| {
+        format!("Failed to remove {}", recovery.path.display())
+    })));
+    for prompt in &prompts {
+        commands.entity(prompt).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animation::Animatable,
+        points::{Points, PointsMaterial, PointsMeshBuilder},
+        project::ProjectSettings,
+    };
+
+    #[test]
+    fn test_recovery_path() {
+        let untitled = recovery_path(None);
+        assert_eq!(untitled.file_name().unwrap(), "untitled.terp");
+        assert!(untitled.parent().unwrap().ends_with("terp/recovery"));
+
+        let named = recovery_path(Some(Path::new("/projects/smile.terp")));
+        assert_eq!(named.parent(), untitled.parent());
+        let name = named.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("smile.terp-"));
+        assert_eq!(
+            named,
+            recovery_path(Some(Path::new("/projects/smile.terp")))
+        );
+        assert_ne!(named, recovery_path(Some(Path::new("/other/smile.terp"))));
+
+        // Deeply nested projects with long names still fit in a file name
+        let deep = format!("/{}/{}.terp", "nested/".repeat(100), "long".repeat(100));
+        let deep = recovery_path(Some(Path::new(&deep)));
+        assert!(deep.file_name().unwrap().len() < 100);
+    }
+
+    #[test]
+    fn test_corrupt_recovery() {
+        let directory = env::temp_dir().join(format!("terp-corrupt-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("untitled.terp");
+        // Truncated by a crash mid-write
+        fs::write(&path, b"TERP\x02").unwrap();
+
+        let mut app = App::new();
+        app.init_resource::<cli::Args>()
+            .init_resource::<Config>()
+            .add_systems(
+                Startup,
+                move |args: Res<cli::Args>, config: Res<Config>, mut commands: Commands| {
+                    let mut recovery = recovery(path.clone());
+                    recover(&mut recovery, &args, &config, &mut commands).unwrap();
+                    commands.insert_resource(recovery);
+                },
+            );
+        app.update();
+
+        let recovery = app.world().resource::<Recovery>();
+        assert!(recovery.pending.is_none());
+        assert!(!recovery.path.exists());
+        assert!(corrupt_path(&recovery.path).exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_track_saved() {
+        let mut app = App::new();
+        app.insert_resource(cli::Args::new(Some("project.terp")))
+            .insert_resource(recovery(PathBuf::from("recovery.terp")))
+            .add_event::<LoadProject>()
+            .add_event::<ProjectSaved>()
+            .add_systems(Update, track_saved.pipe(error_handler));
+        let project = Project {
+            settings: ProjectSettings {
+                duration: 1.0,
+                ..default()
+            },
+            ..default()
+        };
+        let saved = canonical(&project).unwrap();
+
+        // Recovery saves are ignored
+        app.world_mut().send_event(ProjectSaved(SaveProjectData {
+            path: "recovery.terp".into(),
+            data: saved.clone(),
+            backups: 0,
+        }));
+        app.update();
+        assert!(app.world().resource::<Recovery>().saved.is_none());
+
+        app.world_mut().send_event(ProjectSaved(SaveProjectData {
+            path: "project.terp".into(),
+            data: project.to_bytes(Format::Json).unwrap(),
+            backups: 0,
+        }));
+        app.update();
+        assert_eq!(
+            app.world().resource::<Recovery>().saved,
+            Some(saved.clone())
+        );
+
+        // Restored changes are still unsaved
+        app.world_mut().resource_mut::<Recovery>().restoring = true;
+        app.world_mut().send_event(LoadProject(Project::default()));
+        app.update();
+        let recovery = app.world().resource::<Recovery>();
+        assert_eq!(recovery.saved, Some(saved));
+        assert!(!recovery.restoring);
+
+        app.world_mut().send_event(LoadProject(Project::default()));
+        app.update();
+        assert_eq!(
+            app.world().resource::<Recovery>().saved,
+            Some(canonical(&Project::default()).unwrap())
+        );
+    }
+
+    fn recovery(path: PathBuf) -> Recovery {
+        Recovery {
+            path,
+            pending: None,
+            last: None,
+            saved: None,
+            restoring: false,
+        }
+    }
+
+    fn setup(world: &mut World) -> Entity {
+        world.insert_resource(Recovery {
+            saved: Some(canonical(&Config::default().new_project()).unwrap()),
+            ..recovery(PathBuf::from("recovery.terp"))
+        });
+        world.spawn_empty().id()
+    }
+
+    fn add_drawing(world: &mut World, entity: Entity) {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(
+            Mesh::build_interpolated(&Points(vec![Vec2::ZERO]), &Points(vec![Vec2::ONE])).unwrap(),
+        );
+        let material = world
+            .resource_mut::<Assets<PointsMaterial>>()
+            .add(PointsMaterial::default());
+        world.entity_mut(entity).insert((
+            Animatable,
+            Mesh2d(mesh),
+            MeshMaterial2d(material),
+            Transform::default(),
+        ));
+    }
+
+    #[test]
+    fn test_autosave() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<cli::Args>()
+            .init_resource::<Config>()
+            .init_resource::<ProjectSettings>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<PointsMaterial>>()
+            .add_event::<SaveProjectData>()
+            .add_systems(Update, autosave.pipe(error_handler));
+        let entity = setup(app.world_mut());
+
+        // An untouched session has nothing to recover
+        app.update();
+        assert!(app.world().resource::<Events<SaveProjectData>>().is_empty());
+
+        add_drawing(app.world_mut(), entity);
+        app.update();
+        let events = app.world().resource::<Events<SaveProjectData>>();
+        let mut cursor = events.get_cursor();
+        let event = cursor.read(events).last().unwrap();
+        assert_eq!(event.path, Path::new("recovery.terp"));
+        let recovered = Project::from_bytes(&event.data).unwrap();
+        assert_eq!(recovered.drawings.len(), 1);
+
+        // Unchanged since the last autosave
+        app.world_mut()
+            .resource_mut::<Events<SaveProjectData>>()
+            .clear();
+        app.update();
+        assert!(app.world().resource::<Events<SaveProjectData>>().is_empty());
+
+        // Waiting for the user to restore or discard the previous session
+        app.world_mut().resource_mut::<ProjectSettings>().duration = 1.0;
+        app.world_mut().resource_mut::<Recovery>().pending = Some(Vec::new());
+        app.update();
+        assert!(app.world().resource::<Events<SaveProjectData>>().is_empty());
+    }
+}
@@ -1,22 +1,48 @@
 use bevy::{
     ecs::query::QueryData,
     prelude::*,
-    render::{camera::Viewport, view::RenderLayers},
+    render::{
+        camera::{ScalingMode, Viewport},
+        view::RenderLayers,
+    },
 };
 
-use crate::{Interpolated, ui::CameraLayout};
+use crate::{Interpolated, project::ProjectSettings, ui::CameraLayout};
 
 pub(crate) const SOURCE_LAYER: RenderLayers = RenderLayers::layer(1);
 pub(crate) const TARGET_LAYER: RenderLayers = RenderLayers::layer(2);
 
+const CANVAS_COLOR: Color = Color::Srgba(Srgba::rgb(0.4, 0.4, 0.4));
+
+/// Outline of the project canvas, drawn in both editor views
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct CanvasGizmos;
+
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Startup, setup_cameras)
-        // Need to update cameras after UiLayout which is in PostUpdate
-        .add_systems(Last, update_camera_viewports);
+    app.insert_gizmo_config(
+        CanvasGizmos,
+        GizmoConfig {
+            render_layers: SOURCE_LAYER.union(&TARGET_LAYER),
+            ..default()
+        },
+    )
+    .add_systems(Startup, setup_cameras)
+    .add_systems(
+        Update,
+        (
+            apply_settings.run_if(resource_changed::<ProjectSettings>),
+            draw_canvas,
+        ),
+    )
+    // Need to update cameras after UiLayout which is in PostUpdate
+    .add_systems(Last, update_camera_viewports);
 }
 
 pub(super) fn player_plugin(app: &mut App) {
-    app.add_systems(Startup, setup_player_camera);
+    app.add_systems(Startup, setup_player_camera).add_systems(
+        Update,
+        apply_settings.run_if(resource_changed::<ProjectSettings>),
+    );
 }
 
 fn setup_player_camera(mut commands: Commands) {
@@ -45,6 +71,25 @@ fn setup_cameras(mut commands: Commands) {
     ));
 }
 
+// Fit the canvas in each view, over the project background
+fn apply_settings(
+    settings: Res<ProjectSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut projections: Query<&mut OrthographicProjection, With<Interpolated>>,
+) {
+    clear_color.0 = settings.background.into();
+    for mut projection in &mut projections {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: settings.canvas.x,
+            min_height: settings.canvas.y,
+        };
+    }
+}
+
+fn draw_canvas(settings: Res<ProjectSettings>, mut gizmos: Gizmos<CanvasGizmos>) {
+    gizmos.rect_2d(Isometry2d::IDENTITY, settings.canvas, CANVAS_COLOR);
+}
+
 #[derive(QueryData)]
 struct LayoutQuery {
     interpolated: &'static Interpolated,
@@ -130,6 +175,34 @@ mod tests {
         assert_eq!(*results[0].2, Interpolated::Source);
     }
 
+    #[test]
+    fn test_apply_settings() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ClearColor>()
+            .insert_resource(ProjectSettings {
+                canvas: Vec2::new(800.0, 400.0),
+                background: LinearRgba::WHITE,
+                ..default()
+            })
+            .add_systems(Startup, setup_player_camera)
+            .add_systems(Update, apply_settings);
+        app.update();
+
+        assert_eq!(app.world().resource::<ClearColor>().0, Color::WHITE);
+        let projection = app
+            .world_mut()
+            .query::<&OrthographicProjection>()
+            .single(app.world());
+        assert!(matches!(
+            projection.scaling_mode,
+            ScalingMode::AutoMin {
+                min_width: 800.0,
+                min_height: 400.0
+            }
+        ));
+    }
+
     #[test]
     fn test_camera_setup() {
         let mut app = App::new();
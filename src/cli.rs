@@ -1,21 +1,49 @@
-use crate::app;
+use crate::{
+    Interpolated, app,
+    project::{BACKUPS, Project},
+};
+use anyhow::Result;
 use bevy::prelude::*;
-use clap::{Arg, Command, ValueHint};
-use std::path::Path;
+use clap::{Arg, ArgMatches, Command, ValueHint};
+use std::{fs, path::Path};
 
-pub fn parse_cli() -> app::AppPlugin {
-    let matches = Command::new(clap::crate_name!())
+mod convert;
+mod diff;
+mod export;
+mod import;
+mod info;
+mod merge;
+mod settings;
+mod simplify;
+mod transform;
+mod validate;
+
+pub fn parse_cli() -> Cli {
+    let mut matches = Command::new(clap::crate_name!())
         .version(clap::crate_version!())
         .propagate_version(true)
         .subcommand_required(false)
         .arg_required_else_help(false)
         .subcommand(
-            Command::new("editor").arg(
-                Arg::new("project")
-                    .short('p')
-                    .long("project")
-                    .value_hint(ValueHint::FilePath),
-            ),
+            Command::new("editor")
+                .arg(
+                    Arg::new("project")
+                        .short('p')
+                        .long("project")
+                        .value_hint(ValueHint::FilePath),
+                )
+                .arg(
+                    Arg::new("source-svg")
+                        .long("source-svg")
+                        .help("SVG file to import into the source")
+                        .value_hint(ValueHint::FilePath),
+                )
+                .arg(
+                    Arg::new("target-svg")
+                        .long("target-svg")
+                        .help("SVG file to import into the target")
+                        .value_hint(ValueHint::FilePath),
+                ),
         )
         .subcommand(
             Command::new("player").arg(
@@ -24,33 +52,162 @@ pub fn parse_cli() -> app::AppPlugin {
                     .value_hint(ValueHint::FilePath),
             ),
         )
+        .subcommand(convert::command())
+        .subcommand(diff::command())
+        .subcommand(export::command())
+        .subcommand(import::command())
+        .subcommand(info::command())
+        .subcommand(merge::command())
+        .subcommand(settings::command())
+        .subcommand(simplify::command())
+        .subcommand(transform::command())
+        .subcommand(validate::command())
         .get_matches();
 
-    match matches.subcommand() {
-        Some(("editor", editor_matches)) => {
-            app::AppPlugin::Editor(Args::new(editor_matches.get_one::<String>("project")))
+    match matches.remove_subcommand() {
+        Some((name, editor_matches)) if name == "editor" => Cli::App(app::AppPlugin::Editor(
+            Args::new(editor_matches.get_one::<String>("project"))
+                .with_svg(
+                    Interpolated::Source,
+                    editor_matches.get_one::<String>("source-svg"),
+                )
+                .with_svg(
+                    Interpolated::Target,
+                    editor_matches.get_one::<String>("target-svg"),
+                ),
+        )),
+        Some((name, player_matches)) if name == "player" => Cli::App(app::AppPlugin::Player(
+            Args::new(player_matches.get_one::<String>("project")),
+        )),
+        Some((name, convert_matches)) if name == "convert" => {
+            Cli::Tool(convert::run, convert_matches)
+        }
+        Some((name, diff_matches)) if name == "diff" => Cli::Tool(diff::run, diff_matches),
+        Some((name, export_matches)) if name == "export" => Cli::Tool(export::run, export_matches),
+        Some((name, import_matches)) if name == "import" => Cli::Tool(import::run, import_matches),
+        Some((name, info_matches)) if name == "info" => Cli::Tool(info::run, info_matches),
+        Some((name, merge_matches)) if name == "merge" => Cli::Tool(merge::run, merge_matches),
+        Some((name, settings_matches)) if name == "settings" => {
+            Cli::Tool(settings::run, settings_matches)
         }
-        Some(("player", player_matches)) => {
-            app::AppPlugin::Player(Args::new(player_matches.get_one::<String>("project")))
+        Some((name, simplify_matches)) if name == "simplify" => {
+            Cli::Tool(simplify::run, simplify_matches)
         }
-        None => app::AppPlugin::Editor(Args::new::<String>(None)),
+        Some((name, transform_matches)) if name == "transform" => {
+            Cli::Tool(transform::run, transform_matches)
+        }
+        Some((name, validate_matches)) if name == "validate" => {
+            Cli::Tool(validate::run, validate_matches)
+        }
+        None => Cli::App(app::AppPlugin::Editor(Args::new::<String>(None))),
         _ => unreachable!("All commands covered"),
     }
 }
 
+/// Parsed command line, either a windowed app or a command line tool
+pub enum Cli {
+    App(app::AppPlugin),
+    Tool(fn(&ArgMatches) -> Result<()>, ArgMatches),
+}
+
+impl Cli {
+    pub fn run(self) -> AppExit {
+        match self {
+            Cli::App(plugin) => plugin.run(),
+            Cli::Tool(run, matches) => match run(&matches) {
+                Ok(()) => AppExit::Success,
+                Err(err) => {
+                    eprintln!("Error: {:?}", err);
+                    AppExit::error()
+                }
+            },
+        }
+    }
+}
+
+// Values of a repeated option with their positions on the command line
+fn indexed<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, name: &str) -> Vec<(usize, T)> {
+    match (matches.indices_of(name), matches.get_many::<T>(name)) {
+        (Some(indices), Some(values)) => indices.zip(values.cloned()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Whether `output` names the same file as `path`, even if spelled differently
+fn same_file(path: &Path, output: &Path) -> bool {
+    path == output
+        || matches!(
+            (fs::canonicalize(path), fs::canonicalize(output)),
+            (Ok(path), Ok(output)) if path == output
+        )
+}
+
+// Save a project loaded from `path`, backing it up if `output` overwrites it
+fn save_output(project: &Project, path: &Path, output: &Path) -> Result<()> {
+    let backups = if same_file(path, output) { BACKUPS } else { 0 };
+    project.save_with_backups(output, backups)
+}
+
+fn parse_translation(value: &str) -> Result<Vec2, String> {
+    let translation = value
+        .split_once(',')
+        .and_then(|(x, y)| Some(Vec2::new(x.parse().ok()?, y.parse().ok()?)));
+    match translation {
+        Some(translation) if translation.is_finite() => Ok(translation),
+        _ => Err("expected X,Y in pixels (e.g. -200,50)".into()),
+    }
+}
+
+fn parse_duration(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(duration) if duration > 0.0 && duration.is_finite() => Ok(duration),
+        _ => Err("expected a positive number of seconds".into()),
+    }
+}
+
 #[derive(Resource, Clone, Default, Debug)]
 pub struct Args {
     /// Project file
     project: Option<String>,
+    /// SVG file to import into the source
+    source_svg: Option<String>,
+    /// SVG file to import into the target
+    target_svg: Option<String>,
 }
 
 impl Args {
     pub fn new<I: Into<String>>(project: Option<I>) -> Self {
         Self {
             project: project.map(|p| p.into()),
+            ..default()
         }
     }
 
+    pub(crate) fn with_svg<I: Into<String>>(
+        mut self,
+        interpolated: Interpolated,
+        svg: Option<I>,
+    ) -> Self {
+        let svg = svg.map(|s| s.into());
+        match interpolated {
+            Interpolated::Source => self.source_svg = svg,
+            Interpolated::Target => self.target_svg = svg,
+        }
+        self
+    }
+
+    pub(crate) fn svg(&self, interpolated: Interpolated) -> Option<&Path> {
+        match interpolated {
+            Interpolated::Source => self.source_svg.as_deref(),
+            Interpolated::Target => self.target_svg.as_deref(),
+        }
+        .map(Path::new)
+    }
+
+    pub(crate) fn set_project(&mut self, project: Option<&Path>) {
+        self.project = project.map(|p| p.to_string_lossy().into_owned());
+    }
+
     pub fn project(&self) -> Option<&Path> {
         if let Some(ref project) = self.project {
             Some(Path::new(project))
@@ -0,0 +1,272 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgMatches, Command, ValueHint, builder::PossibleValuesParser, value_parser};
+use image::ImageFormat;
+
+use super::same_file;
+use crate::{
+    export,
+    project::{Format, Project},
+    render::Renderer,
+};
+
+/// Frame rate of animated GIFs, as `terp export`
+const FPS: u32 = 25;
+
+const FORMATS: [&str; 9] = [
+    "terp",
+    "ron",
+    "json",
+    "svg",
+    "animated-svg",
+    "png",
+    "gif",
+    "glb",
+    "lottie",
+];
+
+pub(super) fn command() -> Command {
+    Command::new("convert")
+        .about(
+            "Convert projects to another project format or export them, \
+             chosen by --format or the output extension",
+        )
+        .after_help(
+            "Without --output each project is written next to itself, e.g. \
+             `terp convert *.terp --format ron` writes project.terp.ron for each project.terp.\n\
+             A .terp.json output is a project, any other .json output is a Lottie animation.\n\
+             Exports use the project canvas size and the source frame, \
+             `terp export` has more options.",
+        )
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .num_args(1..)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Output file, when converting a single project")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("Format to convert to [default: from the output extension]")
+                .value_parser(PossibleValuesParser::new(FORMATS))
+                .required_unless_present("output"),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let projects: Vec<&PathBuf> = matches
+        .get_many::<PathBuf>("project")
+        .expect("required")
+        .collect();
+    let output = matches.get_one::<PathBuf>("output");
+    if output.is_some() && projects.len() > 1 {
+        bail!("--output can only be used with a single project");
+    }
+    let format = match matches.get_one::<String>("format") {
+        Some(name) => Target::from_name(name),
+        None => {
+            let output = output.expect("required without format");
+            Target::from_path(output)
+                .with_context(|| format!("Unknown format for {}, use --format", output.display()))?
+        }
+    };
+
+    for path in projects {
+        let project = Project::load(path)?;
+        let output = output.cloned().unwrap_or_else(|| output_path(path, format));
+        if same_file(path, &output) {
+            bail!(
+                "Converting {} would overwrite it, use --output",
+                path.display()
+            );
+        }
+        format
+            .write(&project, &output)
+            .with_context(|| format!("Failed to convert {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Target {
+    Project(Format),
+    Svg,
+    AnimatedSvg,
+    Png,
+    Gif,
+    Glb,
+    Lottie,
+}
+
+impl Target {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "terp" => Self::Project(Format::Binary),
+            "ron" => Self::Project(Format::Ron),
+            "json" => Self::Project(Format::Json),
+            "svg" => Self::Svg,
+            "animated-svg" => Self::AnimatedSvg,
+            "png" => Self::Png,
+            "gif" => Self::Gif,
+            "glb" => Self::Glb,
+            "lottie" => Self::Lottie,
+            _ => unreachable!("Validated by clap"),
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(OsStr::to_str)? {
+            "terp" => Some(Self::Project(Format::Binary)),
+            "ron" => Some(Self::Project(Format::Ron)),
+            "json" if Format::from_path(path) == Format::Json => Some(Self::Project(Format::Json)),
+            "json" => Some(Self::Lottie),
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "glb" => Some(Self::Glb),
+            _ => None,
+        }
+    }
+
+    /// Extension of files written in this format, text projects keep the `.terp`
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Project(Format::Binary) => "terp",
+            Self::Project(Format::Ron) => "terp.ron",
+            Self::Project(Format::Json) => "terp.json",
+            Self::Svg | Self::AnimatedSvg => "svg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Glb => "glb",
+            Self::Lottie => "json",
+        }
+    }
+
+    fn write(self, project: &Project, output: &Path) -> Result<()> {
+        let size = project.settings.canvas.ceil().as_uvec2();
+        let data = match self {
+            Self::Project(format) => return project.save_format(output, format),
+            Self::Svg => export::svg::frame(project, 0.0)?.into_bytes(),
+            Self::AnimatedSvg => export::svg::animated(project)?.into_bytes(),
+            Self::Png => {
+                let mut data = Cursor::new(Vec::new());
+                Renderer::new(size)
+                    .render(project, 0.0)
+                    .write_to(&mut data, ImageFormat::Png)?;
+                data.into_inner()
+            }
+            Self::Gif => export::gif::animated(project, size, FPS)?,
+            Self::Glb => export::gltf::glb(project)?,
+            Self::Lottie => export::lottie::animation(project)?,
+        };
+        fs::write(output, data).with_context(|| format!("Failed to write {}", output.display()))
+    }
+}
+
+// Replace the project extensions of `path`, e.g. project.terp.ron to project.svg
+fn output_path(path: &Path, format: Target) -> PathBuf {
+    let name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+    let name = name
+        .strip_suffix(".ron")
+        .or_else(|| name.strip_suffix(".json"))
+        .unwrap_or(name);
+    let name = name.strip_suffix(".terp").unwrap_or(name);
+    path.with_file_name(format!("{}.{}", name, format.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let ron = Target::Project(Format::Ron);
+        assert_eq!(
+            output_path(Path::new("art/smile.terp"), ron),
+            Path::new("art/smile.terp.ron")
+        );
+        assert_eq!(
+            output_path(Path::new("smile.terp.ron"), Target::Project(Format::Binary)),
+            Path::new("smile.terp")
+        );
+        assert_eq!(
+            output_path(Path::new("smile.terp.json"), Target::Lottie),
+            Path::new("smile.json")
+        );
+        assert_eq!(
+            output_path(Path::new("smile"), Target::AnimatedSvg),
+            Path::new("smile.svg")
+        );
+    }
+
+    #[test]
+    fn test_same_file() {
+        let directory = std::env::temp_dir().join(format!("terp-same-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("x.terp");
+        fs::write(&path, []).unwrap();
+        assert!(same_file(&path, &path));
+        assert!(same_file(
+            &path,
+            &directory
+                .join("../")
+                .join(directory.file_name().unwrap())
+                .join("x.terp")
+        ));
+        assert!(!same_file(&path, &directory.join("x.terp.ron")));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_target() {
+        assert_eq!(
+            Target::from_path(Path::new("out.terp.json")),
+            Some(Target::Project(Format::Json))
+        );
+        assert_eq!(
+            Target::from_path(Path::new("out.json")),
+            Some(Target::Lottie)
+        );
+        assert_eq!(Target::from_path(Path::new("out.glb")), Some(Target::Glb));
+        assert_eq!(Target::from_path(Path::new("out.mp4")), None);
+        assert_eq!(Target::from_name("lottie"), Target::Lottie);
+    }
+
+    #[test]
+    fn test_write() {
+        let directory = std::env::temp_dir().join(format!("terp-convert-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let project =
+            Project::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("web/demo/x.terp")).unwrap();
+
+        let ron = directory.join("x.terp.ron");
+        Target::Project(Format::Ron).write(&project, &ron).unwrap();
+        assert!(fs::read_to_string(&ron).unwrap().starts_with('('));
+        assert_eq!(Project::load(&ron).unwrap().drawings.len(), 2);
+
+        let png = directory.join("x.png");
+        Target::Png.write(&project, &png).unwrap();
+        let image = image::open(&png).unwrap();
+        // The demo predates canvases, its canvas grew to fit the drawings
+        let canvas = project.settings.canvas.ceil().as_uvec2();
+        assert_eq!((image.width(), image.height()), (canvas.x, canvas.y));
+        assert_eq!(canvas, bevy::math::UVec2::new(600, 650));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+use std::{cmp::Ordering, fmt, path::PathBuf};
+
+use anyhow::Result;
+use bevy::prelude::*;
+use clap::{Arg, ArgMatches, Command, ValueHint, value_parser};
+
+use crate::{
+    points::{Points, PointsSettings},
+    project::{Drawing, Project},
+};
+
+pub(super) fn command() -> Command {
+    Command::new("diff")
+        .about("Compare two projects drawing by drawing")
+        .after_help(
+            "Drawings are matched by layer, which the editor numbers in drawing order. \
+             Changes are listed with the layer of their drawing.",
+        )
+        .arg(
+            Arg::new("old")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("new")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("tolerance")
+                .long("tolerance")
+                .help("Pixels points and radius may move without being reported")
+                .default_value("0.01")
+                .value_parser(parse_tolerance),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let old = matches.get_one::<PathBuf>("old").expect("required");
+    let new = matches.get_one::<PathBuf>("new").expect("required");
+    let tolerance = *matches.get_one::<f32>("tolerance").expect("defaulted");
+
+    let differences = diff(&Project::load(old)?, &Project::load(new)?, tolerance);
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    match differences.len() {
+        0 => println!("No differences"),
+        1 => println!("1 difference"),
+        count => println!("{} differences", count),
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct Difference {
+    /// Layer of the drawing that changed, or none for the project settings
+    layer: Option<f32>,
+    message: String,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.layer {
+            Some(layer) => write!(f, "layer {}: {}", layer, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+fn diff(old: &Project, new: &Project, tolerance: f32) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let mut setting = |name: &str, old: String, new: String| {
+        if old != new {
+            differences.push(Difference {
+                layer: None,
+                message: format!("{} {} -> {}", name, old, new),
+            });
+        }
+    };
+    let size = |canvas: Vec2| format!("{}x{}", canvas.x, canvas.y);
+    let (old_settings, new_settings) = (&old.settings, &new.settings);
+    setting(
+        "canvas",
+        size(old_settings.canvas),
+        size(new_settings.canvas),
+    );
+    setting(
+        "background",
+        hex(old_settings.background),
+        hex(new_settings.background),
+    );
+    setting(
+        "duration",
+        old_settings.duration.to_string(),
+        new_settings.duration.to_string(),
+    );
+    setting(
+        "easing",
+        format!("{:?}", old_settings.easing),
+        format!("{:?}", new_settings.easing),
+    );
+
+    // Walk both projects in layer order, drawings sharing a layer pair up in project order
+    let (old, new) = (old.layered(), new.layered());
+    let (mut old, mut new) = (old.iter().peekable(), new.iter().peekable());
+    loop {
+        let order = match (old.peek(), new.peek()) {
+            (Some(old), Some(new)) => old.layer.total_cmp(&new.layer),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        let mut drawing = |layer: f32, message: String| {
+            differences.push(Difference {
+                layer: Some(layer),
+                message,
+            });
+        };
+        match order {
+            Ordering::Less => {
+                let removed = old.next().expect("peeked");
+                drawing(removed.layer, format!("removed, {}", point_counts(removed)));
+            }
+            Ordering::Greater => {
+                let added = new.next().expect("peeked");
+                drawing(added.layer, format!("added, {}", point_counts(added)));
+            }
+            Ordering::Equal => {
+                let (old, new) = (old.next().expect("peeked"), new.next().expect("peeked"));
+                let layer = old.layer;
+                let sides = [
+                    (
+                        "source",
+                        (&old.source_points, &old.source_settings),
+                        (&new.source_points, &new.source_settings),
+                    ),
+                    (
+                        "target",
+                        (&old.target_points, &old.target_settings),
+                        (&new.target_points, &new.target_settings),
+                    ),
+                ];
+                for (side, old, new) in sides {
+                    for message in side_differences(old, new, tolerance) {
+                        drawing(layer, format!("{} {}", side, message));
+                    }
+                }
+            }
+        }
+    }
+    differences
+}
+
+fn point_counts(drawing: &Drawing) -> String {
+    format!(
+        "{} source and {} target points",
+        drawing.source_points.0.len(),
+        drawing.target_points.0.len()
+    )
+}
+
+fn side_differences(
+    (old_points, old_settings): (&Points, &PointsSettings),
+    (new_points, new_settings): (&Points, &PointsSettings),
+    tolerance: f32,
+) -> Vec<String> {
+    let mut differences = Vec::new();
+    let (old_color, new_color) = (hex(old_settings.color), hex(new_settings.color));
+    if old_color != new_color {
+        differences.push(format!("color {} -> {}", old_color, new_color));
+    }
+    if (old_settings.radius - new_settings.radius).abs() > tolerance {
+        differences.push(format!(
+            "radius {} -> {}",
+            old_settings.radius, new_settings.radius
+        ));
+    }
+
+    let (old_count, new_count) = (old_points.0.len(), new_points.0.len());
+    if old_count == new_count {
+        let distances: Vec<f32> = old_points
+            .0
+            .iter()
+            .zip(&new_points.0)
+            .map(|(old, new)| old.distance(*new))
+            .filter(|distance| *distance > tolerance)
+            .collect();
+        if let Some(furthest) = distances.iter().copied().reduce(f32::max) {
+            differences.push(format!(
+                "{} of {} points moved, up to {} pixels",
+                distances.len(),
+                old_count,
+                round(furthest)
+            ));
+        }
+    } else {
+        // Points don't pair up, compare the strokes through them instead
+        let furthest = [(old_points, new_points), (new_points, old_points)]
+            .into_iter()
+            .flat_map(|(points, stroke)| {
+                points.0.iter().filter_map(|point| stroke.distance(*point))
+            })
+            .reduce(f32::max);
+        differences.push(match furthest {
+            Some(furthest) if furthest > tolerance => format!(
+                "{} -> {} points, the stroke moved up to {} pixels",
+                old_count,
+                new_count,
+                round(furthest)
+            ),
+            Some(_) => format!(
+                "{} -> {} points, the stroke is unchanged",
+                old_count, new_count
+            ),
+            None => format!("{} -> {} points", old_count, new_count),
+        });
+    }
+    differences
+}
+
+fn hex(color: LinearRgba) -> String {
+    Srgba::from(color).to_hex()
+}
+
+// Distances to two decimal places, to keep the output readable
+fn round(distance: f32) -> f32 {
+    (distance * 100.0).round() / 100.0
+}
+
+fn parse_tolerance(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(tolerance) if tolerance >= 0.0 && tolerance.is_finite() => Ok(tolerance),
+        _ => Err("expected a number of pixels, 0 or more".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawing(layer: f32, source: Vec<Vec2>, target: Vec<Vec2>) -> Drawing {
+        let settings = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 2.0,
+        };
+        Drawing {
+            source_settings: settings,
+            target_settings: settings,
+            source_points: Points(source),
+            target_points: Points(target),
+            layer,
+        }
+    }
+
+    fn messages(old: &Project, new: &Project) -> Vec<String> {
+        diff(old, new, 0.01)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let line = vec![Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(20.0, 0.0)];
+        let old = Project {
+            drawings: vec![
+                drawing(2.0, line.clone(), line.clone()),
+                drawing(1.0, line.clone(), line.clone()),
+                drawing(3.0, vec![Vec2::ZERO], vec![Vec2::ONE]),
+            ],
+            ..default()
+        };
+        assert_eq!(messages(&old, &old), Vec::<String>::new());
+
+        let mut changed = drawing(2.0, line.clone(), vec![line[0], line[2]]);
+        changed.source_points.0[1].y = 3.5;
+        changed.source_settings.color = LinearRgba::RED;
+        changed.target_settings.radius = 3.0;
+        let mut new = Project {
+            drawings: vec![
+                drawing(1.0, line.clone(), line.clone()),
+                changed,
+                drawing(4.0, vec![Vec2::ZERO], vec![Vec2::ONE]),
+            ],
+            ..default()
+        };
+        new.settings.duration = 5.0;
+        assert_eq!(
+            messages(&old, &new),
+            vec![
+                "duration 2.5 -> 5",
+                "layer 2: source color #FFFFFF -> #FF0000",
+                "layer 2: source 1 of 3 points moved, up to 3.5 pixels",
+                "layer 2: target radius 2 -> 3",
+                "layer 2: target 3 -> 2 points, the stroke is unchanged",
+                "layer 3: removed, 1 source and 1 target points",
+                "layer 4: added, 1 source and 1 target points",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_side_differences() {
+        let settings = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 2.0,
+        };
+        let old = Points(vec![Vec2::ZERO, Vec2::new(10.0, 0.0)]);
+        let moved = Points(vec![Vec2::new(0.0, 0.05), Vec2::new(10.0, 0.0)]);
+        // Within tolerance
+        assert_eq!(
+            side_differences((&old, &settings), (&moved, &settings), 0.1),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            side_differences((&old, &settings), (&moved, &settings), 0.01),
+            vec!["1 of 2 points moved, up to 0.05 pixels"]
+        );
+
+        let bent = Points(vec![Vec2::ZERO, Vec2::new(5.0, 4.0), Vec2::new(10.0, 0.0)]);
+        assert_eq!(
+            side_differences((&old, &settings), (&bent, &settings), 0.01),
+            vec!["2 -> 3 points, the stroke moved up to 4 pixels"]
+        );
+        assert_eq!(
+            side_differences((&old, &settings), (&Points(vec![]), &settings), 0.01),
+            vec!["2 -> 0 points"]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, BufWriter, Cursor},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use bevy::math::UVec2;
+use clap::{Arg, ArgAction, ArgMatches, Command, ValueHint, parser::ValueSource, value_parser};
+use image::ImageFormat;
+
+use super::parse_duration;
+use crate::{
+    export,
+    project::{Format, Project},
+    render::Renderer,
+};
+
+/// Default frame size of sprite sheets, so a full cycle at the default fps fits in
+/// `sprite_sheet::MAX_SIZE`
+const SPRITE_SIZE: u32 = 300;
+
+pub(super) fn command() -> Command {
+    Command::new("export")
+        .about("Export a project, the format is chosen by the output extension (.svg, .png, .gif, .glb, .json)")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("interpolation")
+                .short('t')
+                .long("interpolation")
+                .help("Frame to export, from 0 (or \"source\") to 1 (or \"target\")")
+                .default_value("source")
+                .value_parser(parse_interpolation),
+        )
+        .arg(
+            Arg::new("animated")
+                .long("animated")
+                .help("Export the whole animation as a self animating SVG")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("interpolation"),
+        )
+        .arg(
+            Arg::new("gif")
+                .long("gif")
+                .help(
+                    "Export the whole animation as an animated GIF, whatever the output extension",
+                )
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["interpolation", "animated"]),
+        )
+        .arg(
+            Arg::new("sequence")
+                .long("sequence")
+                .help(
+                    "Export frames for video encoders, as PNG files in the output directory \
+                     or as Y4M video if the output is .y4m (or - for stdout)",
+                )
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["interpolation", "animated", "gif"]),
+        )
+        .arg(
+            Arg::new("sprite-sheet")
+                .long("sprite-sheet")
+                .help(
+                    "Export the whole animation as a PNG sprite sheet, \
+                     with a JSON atlas of frame positions and timings next to it",
+                )
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["interpolation", "animated", "gif", "sequence"]),
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .help("Frames per row of the sprite sheet [default: a square grid]")
+                .value_parser(value_parser!(u32).range(1..))
+                .requires("sprite-sheet"),
+        )
+        .arg(
+            Arg::new("duration")
+                .long("duration")
+                .help("Seconds of animation in a sequence [default: one full cycle]")
+                .value_parser(parse_duration)
+                .requires("sequence"),
+        )
+        .arg(
+            Arg::new("fps")
+                .long("fps")
+                .help("Frames per second of animated images")
+                // GIF frame delays are in hundredths of a second, 25 divides evenly
+                .default_value("25")
+                .value_parser(value_parser!(u32).range(1..=100)),
+        )
+        .arg(
+            Arg::new("width")
+                .long("width")
+                .help("Width in pixels of rendered images [sprite sheet frames default to 300]")
+                .default_value("600")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("height")
+                .long("height")
+                .help("Height in pixels of rendered images [sprite sheet frames default to 300]")
+                .default_value("600")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let project = Project::load(matches.get_one::<PathBuf>("project").expect("required"))?;
+    let output = matches.get_one::<PathBuf>("output").expect("required");
+    let t = *matches.get_one::<f32>("interpolation").expect("defaulted");
+    let animated = matches.get_flag("animated");
+    let fps = *matches.get_one::<u32>("fps").expect("defaulted");
+    let size = UVec2::new(
+        *matches.get_one::<u32>("width").expect("defaulted"),
+        *matches.get_one::<u32>("height").expect("defaulted"),
+    );
+
+    if matches.get_flag("sequence") {
+        let length = matches
+            .get_one::<f32>("duration")
+            .copied()
+            .unwrap_or(project.settings.duration * 2.0);
+        let frames = export::frames(&project.settings, fps as f32, length)?;
+        return sequence(&project, size, fps, &frames, output);
+    }
+    if matches.get_flag("sprite-sheet") {
+        let cycle = project.settings.duration * 2.0;
+        let frames = export::frames(&project.settings, fps as f32, cycle)?;
+        let columns = matches.get_one::<u32>("columns").copied();
+        let size = UVec2::new(
+            sprite_size(matches, "width", size.x),
+            sprite_size(matches, "height", size.y),
+        );
+        return sprite_sheet(&project, size, fps, &frames, columns, output);
+    }
+
+    let data = match output.extension().and_then(OsStr::to_str) {
+        _ if matches.get_flag("gif") => export::gif::animated(&project, size, fps)?,
+        Some("gif") => export::gif::animated(&project, size, fps)?,
+        Some("glb") => export::gltf::glb(&project)?,
+        Some("json") if Format::from_path(output) == Format::Json => {
+            bail!("{} is a project name, use terp convert", output.display())
+        }
+        Some("json") => export::lottie::animation(&project)?,
+        Some("svg") if animated => export::svg::animated(&project)?.into_bytes(),
+        Some("svg") => export::svg::frame(&project, t)?.into_bytes(),
+        Some("png") if animated => {
+            bail!("PNG export is a single frame, --animated is not supported")
+        }
+        Some("png") => {
+            let mut data = Cursor::new(Vec::new());
+            Renderer::new(size)
+                .render(&project, t)
+                .write_to(&mut data, ImageFormat::Png)?;
+            data.into_inner()
+        }
+        _ => bail!("Unsupported export format {}", output.display()),
+    };
+    fs::write(output, data).with_context(|| format!("Failed to write {}", output.display()))
+}
+
+// Frames are packed into one image, so they are smaller than other exports unless given
+fn sprite_size(matches: &ArgMatches, name: &str, size: u32) -> u32 {
+    match matches.value_source(name) {
+        Some(ValueSource::DefaultValue) => SPRITE_SIZE,
+        _ => size,
+    }
+}
+
+fn sequence(project: &Project, size: UVec2, fps: u32, frames: &[f32], output: &Path) -> Result<()> {
+    if output == Path::new("-") {
+        return Ok(export::sequence::y4m(
+            project,
+            size,
+            fps,
+            frames,
+            BufWriter::new(io::stdout().lock()),
+        )?);
+    }
+    match output.extension().and_then(OsStr::to_str) {
+        Some("y4m") => {
+            let file = fs::File::create(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            export::sequence::y4m(project, size, fps, frames, BufWriter::new(file))
+                .with_context(|| format!("Failed to write {}", output.display()))
+        }
+        _ => export::sequence::png(project, size, frames, output),
+    }
+}
+
+fn sprite_sheet(
+    project: &Project,
+    size: UVec2,
+    fps: u32,
+    frames: &[f32],
+    columns: Option<u32>,
+    output: &Path,
+) -> Result<()> {
+    if output.extension().and_then(OsStr::to_str) != Some("png") {
+        bail!(
+            "Sprite sheets are PNG, unsupported output {}",
+            output.display()
+        );
+    }
+    let image = output
+        .file_name()
+        .and_then(OsStr::to_str)
+        .context("Invalid output name")?;
+    let sheet = export::sprite_sheet::render(project, size, fps, frames, columns, image)?;
+    sheet
+        .image
+        .save(output)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    let atlas = output.with_extension("json");
+    let mut data = serde_json::to_vec_pretty(&sheet.atlas)?;
+    data.push(b'\n');
+    fs::write(&atlas, data).with_context(|| format!("Failed to write {}", atlas.display()))
+}
+
+fn parse_interpolation(value: &str) -> Result<f32, String> {
+    match value {
+        "source" => Ok(0.0),
+        "target" => Ok(1.0),
+        _ => match value.parse::<f32>() {
+            Ok(t) if (0.0..=1.0).contains(&t) => Ok(t),
+            _ => Err("expected \"source\", \"target\" or a number from 0 to 1".into()),
+        },
+    }
+}
@@ -0,0 +1,82 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use clap::{Arg, ArgGroup, ArgMatches, Command, ValueHint, value_parser};
+
+use crate::{
+    import::{self, Stroke, svg},
+    points::PointsSettings,
+    project::Project,
+};
+
+pub(super) fn command() -> Command {
+    Command::new("import")
+        .about("Import SVG paths as drawings, pairing source and target paths in document order")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .help("Project to add drawings to, created if it does not exist")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("source")
+                .long("source")
+                .help("SVG file to import into the source")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .help("SVG file to import into the target")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .group(
+            ArgGroup::new("svg")
+                .args(["source", "target"])
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            Arg::new("radius")
+                .long("radius")
+                .help("Radius of paths without a stroke")
+                .default_value("2")
+                .value_parser(value_parser!(f32)),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("project").expect("required");
+    let mut project = if path.exists() {
+        Project::load(path)?
+    } else {
+        Project::default()
+    };
+    let default = PointsSettings {
+        color: LinearRgba::WHITE,
+        radius: *matches.get_one::<f32>("radius").expect("defaulted"),
+    };
+    let read = |name: &str| -> Result<Vec<Stroke>> {
+        let Some(svg) = matches.get_one::<PathBuf>(name) else {
+            return Ok(Vec::new());
+        };
+        let data = fs::read(svg).with_context(|| format!("Failed to read {}", svg.display()))?;
+        svg::strokes(&data, default).with_context(|| format!("Failed to import {}", svg.display()))
+    };
+
+    // Layer imported drawings above the existing ones
+    let layer = project
+        .drawings
+        .iter()
+        .map(|drawing| drawing.layer)
+        .fold(0.0, f32::max)
+        + 1.0;
+    project
+        .drawings
+        .extend(import::pair(read("source")?, read("target")?, layer));
+    project.save(path)
+}
@@ -0,0 +1,337 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use bevy::prelude::*;
+use clap::{Arg, ArgAction, ArgMatches, Command, ValueHint, value_parser};
+use serde::Serialize;
+
+use crate::{
+    points::{Points, PointsSettings},
+    project::{Drawing, Project},
+};
+
+pub(super) fn command() -> Command {
+    Command::new("info")
+        .about("Show a summary of a project's drawings")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print JSON for scripts")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("project").expect("required");
+    let info = Info::new(path, &Project::load(path)?);
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print!("{}", info);
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Info {
+    project: PathBuf,
+    canvas: [f32; 2],
+    background: String,
+    duration: f32,
+    easing: String,
+    drawings: usize,
+    points: Sides<usize>,
+    /// Bounds including point radius, absent if there are no points
+    bounds: Sides<Option<Bounds>>,
+    radius: Sides<Option<Range>>,
+    /// Distinct colors
+    colors: Sides<Vec<String>>,
+    /// Drawings back to front
+    layers: Vec<Layer>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Sides<T> {
+    source: T,
+    target: T,
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+struct Bounds {
+    min: [f32; 2],
+    max: [f32; 2],
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+struct Range {
+    min: f32,
+    max: f32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Layer {
+    layer: f32,
+    points: Sides<usize>,
+    bounds: Sides<Option<Bounds>>,
+}
+
+impl Info {
+    fn new(path: &Path, project: &Project) -> Self {
+        let layered = project.layered();
+        let sides = |side: fn(&Drawing) -> (&Points, &PointsSettings)| {
+            let drawings: Vec<_> = layered.iter().map(|drawing| side(drawing)).collect();
+            let radius = drawings
+                .iter()
+                .map(|(_, settings)| Range {
+                    min: settings.radius,
+                    max: settings.radius,
+                })
+                .reduce(|a, b| Range {
+                    min: a.min.min(b.min),
+                    max: a.max.max(b.max),
+                });
+            let mut colors: Vec<String> = drawings
+                .iter()
+                .map(|(_, settings)| hex(settings.color))
+                .collect();
+            colors.sort();
+            colors.dedup();
+            (
+                drawings.iter().map(|(points, _)| points.0.len()).sum(),
+                drawings
+                    .iter()
+                    .filter_map(|(points, settings)| bounds(points, settings))
+                    .reduce(|a, b| a.union(b))
+                    .map(Bounds::from),
+                radius,
+                colors,
+            )
+        };
+        let (source_points, source_bounds, source_radius, source_colors) = sides(source);
+        let (target_points, target_bounds, target_radius, target_colors) = sides(target);
+
+        let settings = &project.settings;
+        Self {
+            project: path.into(),
+            canvas: settings.canvas.into(),
+            background: hex(settings.background),
+            duration: settings.duration,
+            easing: format!("{:?}", settings.easing),
+            drawings: project.drawings.len(),
+            points: Sides {
+                source: source_points,
+                target: target_points,
+            },
+            bounds: Sides {
+                source: source_bounds,
+                target: target_bounds,
+            },
+            radius: Sides {
+                source: source_radius,
+                target: target_radius,
+            },
+            colors: Sides {
+                source: source_colors,
+                target: target_colors,
+            },
+            layers: layered
+                .iter()
+                .map(|drawing| {
+                    let (source_points, source_settings) = source(drawing);
+                    let (target_points, target_settings) = target(drawing);
+                    Layer {
+                        layer: drawing.layer,
+                        points: Sides {
+                            source: source_points.0.len(),
+                            target: target_points.0.len(),
+                        },
+                        bounds: Sides {
+                            source: bounds(source_points, source_settings).map(Bounds::from),
+                            target: bounds(target_points, target_settings).map(Bounds::from),
+                        },
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn source(drawing: &Drawing) -> (&Points, &PointsSettings) {
+    (&drawing.source_points, &drawing.source_settings)
+}
+
+fn target(drawing: &Drawing) -> (&Points, &PointsSettings) {
+    (&drawing.target_points, &drawing.target_settings)
+}
+
+// Area covered by the points' discs
+fn bounds(points: &Points, settings: &PointsSettings) -> Option<Rect> {
+    points.bounds().map(|rect| rect.inflate(settings.radius))
+}
+
+fn hex(color: LinearRgba) -> String {
+    Srgba::from(color).with_alpha(1.0).to_hex()
+}
+
+impl From<Rect> for Bounds {
+    fn from(rect: Rect) -> Self {
+        Self {
+            min: rect.min.into(),
+            max: rect.max.into(),
+        }
+    }
+}
+
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) to ({}, {})",
+            self.min[0], self.min[1], self.max[0], self.max[1]
+        )
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{} to {}", self.min, self.max)
+        }
+    }
+}
+
+// Show an optional value, or "none"
+fn or_none<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "none".into(), ToString::to_string)
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "project: {}", self.project.display())?;
+        writeln!(f, "canvas: {}x{}", self.canvas[0], self.canvas[1])?;
+        writeln!(f, "background: {}", self.background)?;
+        writeln!(f, "duration: {}", self.duration)?;
+        writeln!(f, "easing: {}", self.easing)?;
+        writeln!(f, "drawings: {}", self.drawings)?;
+        writeln!(
+            f,
+            "points: {} source, {} target",
+            self.points.source, self.points.target
+        )?;
+        writeln!(f, "source bounds: {}", or_none(&self.bounds.source))?;
+        writeln!(f, "target bounds: {}", or_none(&self.bounds.target))?;
+        writeln!(f, "source radius: {}", or_none(&self.radius.source))?;
+        writeln!(f, "target radius: {}", or_none(&self.radius.target))?;
+        writeln!(f, "source colors: {}", self.colors.source.join(", "))?;
+        writeln!(f, "target colors: {}", self.colors.target.join(", "))?;
+        writeln!(f, "layers (back to front):")?;
+        for layer in &self.layers {
+            writeln!(
+                f,
+                "  {}: {} source points in {}, {} target points in {}",
+                layer.layer,
+                layer.points.source,
+                or_none(&layer.bounds.source),
+                layer.points.target,
+                or_none(&layer.bounds.target),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> Project {
+        Project {
+            drawings: vec![
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::RED,
+                        radius: 2.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::BLUE,
+                        radius: 4.0,
+                    },
+                    source_points: Points(vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 10.0)]),
+                    target_points: Points(vec![Vec2::new(10.0, 0.0), Vec2::new(20.0, -10.0)]),
+                    layer: 2.0,
+                },
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::RED,
+                        radius: 1.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::RED,
+                        radius: 1.0,
+                    },
+                    source_points: Points(vec![Vec2::new(-5.0, 5.0)]),
+                    target_points: Points(vec![Vec2::new(5.0, 5.0)]),
+                    layer: 1.0,
+                },
+            ],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_info() {
+        let info = Info::new(Path::new("test.terp"), &project());
+        assert_eq!(info.drawings, 2);
+        assert_eq!(
+            info.points,
+            Sides {
+                source: 3,
+                target: 3
+            }
+        );
+        assert_eq!(
+            info.bounds.source,
+            Some(Bounds {
+                min: [-6.0, -2.0],
+                max: [12.0, 12.0]
+            })
+        );
+        assert_eq!(info.radius.target, Some(Range { min: 1.0, max: 4.0 }));
+        assert_eq!(info.colors.source, vec!["#FF0000"]);
+        assert_eq!(info.colors.target, vec!["#0000FF", "#FF0000"]);
+        // Back to front
+        assert_eq!(info.layers[0].layer, 1.0);
+        assert_eq!(info.layers[1].points.target, 2);
+        assert_eq!(info.easing, "CubicInOut");
+
+        let text = info.to_string();
+        assert!(text.contains("points: 3 source, 3 target\n"));
+        assert!(text.contains("  2: 2 source points in (-2, -2) to (12, 12), "));
+    }
+
+    #[test]
+    fn test_info_json() {
+        let json = serde_json::to_value(Info::new(Path::new("test.terp"), &project())).unwrap();
+        assert_eq!(json["canvas"], serde_json::json!([600.0, 600.0]));
+        assert_eq!(json["points"]["source"], 3);
+        assert_eq!(json["layers"][1]["bounds"]["target"]["max"][0], 24.0);
+
+        let empty =
+            serde_json::to_value(Info::new(Path::new("empty.terp"), &Project::default())).unwrap();
+        assert_eq!(empty["bounds"]["source"], serde_json::Value::Null);
+        assert_eq!(empty["layers"], serde_json::json!([]));
+    }
+}
@@ -0,0 +1,305 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use bevy::prelude::*;
+use clap::{Arg, ArgAction, ArgMatches, Command, ValueHint, value_parser};
+
+use super::{indexed, parse_translation};
+use crate::project::Project;
+
+pub(super) fn command() -> Command {
+    Command::new("merge")
+        .about(
+            "Combine projects into one, layering each project's drawings above the ones before it",
+        )
+        .after_help(
+            "--translate, --scale and --layer-offset apply to the project before them, e.g. \
+             `terp merge scene.terp tree.terp --translate=-200,0 --scale 0.5 -o composite.terp`.\n\
+             Settings (canvas, background, duration and easing) come from the first project.",
+        )
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .num_args(1..)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .required(true)
+                .help("Project to write")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("translate")
+                .long("translate")
+                .help("Move the project's drawings by X,Y pixels, y up")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .value_parser(parse_translation),
+        )
+        .arg(
+            Arg::new("scale")
+                .long("scale")
+                .help("Scale the project's drawings and point radius about the canvas center")
+                .action(ArgAction::Append)
+                .value_parser(parse_scale),
+        )
+        .arg(
+            Arg::new("layer-offset")
+                .long("layer-offset")
+                .help("Add to the project's layers [default: just above the projects before it]")
+                .action(ArgAction::Append)
+                .allow_negative_numbers(true)
+                .value_parser(parse_layer_offset),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let paths: Vec<&PathBuf> = matches
+        .get_many::<PathBuf>("project")
+        .expect("required")
+        .collect();
+    let output = matches.get_one::<PathBuf>("output").expect("required");
+
+    let placements = placements(matches)?;
+    let mut inputs = Vec::new();
+    for (path, placement) in paths.iter().zip(placements) {
+        inputs.push((Project::load(path)?, placement));
+    }
+    let project = merge(inputs);
+    project.save(output)?;
+    println!(
+        "Merged {} drawings from {} project{} into {}",
+        project.drawings.len(),
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" },
+        output.display()
+    );
+    Ok(())
+}
+
+/// Where a project's drawings go in the merged project
+#[derive(Debug, PartialEq)]
+struct Placement {
+    translation: Vec2,
+    scale: f32,
+    /// Added to each layer, or none to layer the drawings above the projects before
+    layer_offset: Option<f32>,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            scale: 1.0,
+            layer_offset: None,
+        }
+    }
+}
+
+// Placement of each project, from the options following it
+fn placements(matches: &ArgMatches) -> Result<Vec<Placement>> {
+    let projects: Vec<usize> = matches.indices_of("project").expect("required").collect();
+    let mut placements: Vec<Placement> = projects.iter().map(|_| default()).collect();
+    // Index of the project an option at `index` applies to
+    let project = |name: &str, index: usize| -> Result<usize> {
+        match projects.iter().rposition(|project| *project < index) {
+            Some(project) => Ok(project),
+            None => bail!("--{} must follow the project it applies to", name),
+        }
+    };
+
+    for (index, translation) in indexed::<Vec2>(matches, "translate") {
+        placements[project("translate", index)?].translation = translation;
+    }
+    for (index, scale) in indexed::<f32>(matches, "scale") {
+        placements[project("scale", index)?].scale = scale;
+    }
+    for (index, offset) in indexed::<f32>(matches, "layer-offset") {
+        placements[project("layer-offset", index)?].layer_offset = Some(offset);
+    }
+    Ok(placements)
+}
+
+fn merge(inputs: impl IntoIterator<Item = (Project, Placement)>) -> Project {
+    let mut merged: Option<Project> = None;
+    for (project, placement) in inputs {
+        let Some(merged) = &mut merged else {
+            merged = Some(place(project, &placement, 0.0));
+            continue;
+        };
+        // Layer the project's lowest drawing just above the highest so far
+        let top = merged
+            .drawings
+            .iter()
+            .map(|drawing| drawing.layer)
+            .reduce(f32::max);
+        let bottom = project
+            .drawings
+            .iter()
+            .map(|drawing| drawing.layer)
+            .reduce(f32::min);
+        let offset = match (top, bottom) {
+            (Some(top), Some(bottom)) => top + 1.0 - bottom,
+            _ => 0.0,
+        };
+        let project = place(project, &placement, offset);
+        merged.drawings.extend(project.drawings);
+    }
+    merged.unwrap_or_default()
+}
+
+// Move a project's drawings into place, `layer_offset` is used unless the placement has its own
+fn place(mut project: Project, placement: &Placement, layer_offset: f32) -> Project {
+    let Placement {
+        translation,
+        scale,
+        layer_offset: placed_offset,
+    } = *placement;
+    for drawing in &mut project.drawings {
+        let points = drawing
+            .source_points
+            .0
+            .iter_mut()
+            .chain(drawing.target_points.0.iter_mut());
+        for point in points {
+            *point = *point * scale + translation;
+        }
+        drawing.source_settings.radius *= scale;
+        drawing.target_settings.radius *= scale;
+        drawing.layer += placed_offset.unwrap_or(layer_offset);
+    }
+    project
+}
+
+fn parse_scale(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err("expected a positive number".into()),
+    }
+}
+
+fn parse_layer_offset(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(offset) if offset.is_finite() => Ok(offset),
+        _ => Err("expected a number".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        points::{Points, PointsSettings},
+        project::Drawing,
+    };
+
+    fn project(layers: &[f32]) -> Project {
+        let settings = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 2.0,
+        };
+        Project {
+            drawings: layers
+                .iter()
+                .map(|layer| Drawing {
+                    source_settings: settings,
+                    target_settings: settings,
+                    source_points: Points(vec![Vec2::ZERO, Vec2::ONE]),
+                    target_points: Points(vec![Vec2::X, Vec2::Y]),
+                    layer: *layer,
+                })
+                .collect(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_placements() {
+        let matches = command()
+            .try_get_matches_from([
+                "merge",
+                "scene.terp",
+                "tree.terp",
+                "--translate",
+                "-200,50",
+                "--scale",
+                "0.5",
+                "house.terp",
+                "--layer-offset",
+                "-10",
+                "-o",
+                "composite.terp",
+            ])
+            .unwrap();
+        assert_eq!(
+            placements(&matches).unwrap(),
+            vec![
+                Placement::default(),
+                Placement {
+                    translation: Vec2::new(-200.0, 50.0),
+                    scale: 0.5,
+                    layer_offset: None,
+                },
+                Placement {
+                    layer_offset: Some(-10.0),
+                    ..default()
+                },
+            ]
+        );
+
+        let matches = command()
+            .try_get_matches_from(["merge", "--scale", "2", "scene.terp", "-o", "out.terp"])
+            .unwrap();
+        assert!(placements(&matches).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut scene = project(&[1.0, 2.0]);
+        scene.settings.duration = 5.0;
+        let mut tree = project(&[1.0, 3.0]);
+        tree.settings.duration = 1.0;
+        let merged = merge([
+            (scene, Placement::default()),
+            (
+                tree,
+                Placement {
+                    translation: Vec2::new(10.0, 20.0),
+                    scale: 2.0,
+                    layer_offset: None,
+                },
+            ),
+            (
+                project(&[1.0]),
+                Placement {
+                    layer_offset: Some(0.5),
+                    ..default()
+                },
+            ),
+            (project(&[]), Placement::default()),
+            (project(&[0.0]), Placement::default()),
+        ]);
+
+        // Settings come from the first project
+        assert_eq!(merged.settings.duration, 5.0);
+        let layers: Vec<f32> = merged
+            .drawings
+            .iter()
+            .map(|drawing| drawing.layer)
+            .collect();
+        assert_eq!(layers, vec![1.0, 2.0, 3.0, 5.0, 1.5, 6.0]);
+        let tree = &merged.drawings[2];
+        assert_eq!(
+            tree.source_points.0,
+            [Vec2::new(10.0, 20.0), Vec2::new(12.0, 22.0)]
+        );
+        assert_eq!(tree.target_points.0[0], Vec2::new(12.0, 20.0));
+        assert_eq!(tree.target_settings.radius, 4.0);
+        assert_eq!(merged.drawings[0].source_points.0[1], Vec2::ONE);
+    }
+}
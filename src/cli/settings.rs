@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use clap::{Arg, ArgMatches, Command, ValueHint, value_parser};
+
+use super::parse_duration;
+use crate::project::Project;
+
+pub(super) fn command() -> Command {
+    Command::new("settings")
+        .about("Show or change project settings, which the player, editor and exports all use")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("canvas")
+                .long("canvas")
+                .help("Canvas size as WIDTHxHEIGHT, or \"fit\" to fit the drawings")
+                .value_parser(parse_canvas),
+        )
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .help("Background color as hex, e.g. \"#2b2c2f\"")
+                .value_parser(parse_color),
+        )
+        .arg(
+            Arg::new("duration")
+                .long("duration")
+                .help("Seconds to animate from source to target")
+                .value_parser(parse_duration),
+        )
+        .arg(
+            Arg::new("easing")
+                .long("easing")
+                .help(
+                    "Easing from source to target, e.g. \"Linear\", \"SineInOut\" or \"Steps(4)\"",
+                )
+                .value_parser(parse_easing),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("project").expect("required");
+    let mut project = Project::load(path)?;
+    let mut changed = false;
+    if let Some(canvas) = matches.get_one::<Canvas>("canvas") {
+        project.settings.canvas = match canvas {
+            Canvas::Size(size) => *size,
+            // The canvas is centered on the origin, so it must reach the furthest edge both ways
+            Canvas::Fit => {
+                let bounds = project
+                    .bounds()
+                    .context("Nothing to fit, the project has no drawings")?;
+                (bounds.min.abs().max(bounds.max.abs()) * 2.0).ceil()
+            }
+        };
+        changed = true;
+    }
+    if let Some(background) = matches.get_one::<LinearRgba>("background") {
+        project.settings.background = *background;
+        changed = true;
+    }
+    if let Some(duration) = matches.get_one::<f32>("duration") {
+        project.settings.duration = *duration;
+        changed = true;
+    }
+    if let Some(easing) = matches.get_one::<EaseFunction>("easing") {
+        project.settings.easing = *easing;
+        changed = true;
+    }
+
+    if changed {
+        project.save(path)?;
+    }
+    let settings = &project.settings;
+    println!("canvas: {}x{}", settings.canvas.x, settings.canvas.y);
+    println!(
+        "background: {}",
+        Srgba::from(settings.background).with_alpha(1.0).to_hex()
+    );
+    println!("duration: {}", settings.duration);
+    println!("easing: {:?}", settings.easing);
+    Ok(())
+}
+
+#[derive(Clone)]
+enum Canvas {
+    Size(Vec2),
+    Fit,
+}
+
+fn parse_canvas(value: &str) -> Result<Canvas, String> {
+    if value == "fit" {
+        return Ok(Canvas::Fit);
+    }
+    let size = value
+        .split_once('x')
+        .and_then(|(width, height)| Some(Vec2::new(width.parse().ok()?, height.parse().ok()?)));
+    match size {
+        Some(size) if size.cmpgt(Vec2::ZERO).all() && size.is_finite() => Ok(Canvas::Size(size)),
+        _ => Err("expected WIDTHxHEIGHT (e.g. 800x600) or \"fit\"".into()),
+    }
+}
+
+fn parse_color(value: &str) -> Result<LinearRgba, String> {
+    Srgba::hex(value)
+        .map(LinearRgba::from)
+        .map_err(|err| err.to_string())
+}
+
+fn parse_easing(value: &str) -> Result<EaseFunction, String> {
+    ron::from_str(value).map_err(|_| format!("unknown easing function \"{}\"", value))
+}
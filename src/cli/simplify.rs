@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command, ValueHint, value_parser};
+
+use super::save_output;
+use crate::project::Project;
+
+pub(super) fn command() -> Command {
+    Command::new("simplify")
+        .about("Remove redundant points from drawings, keeping source and target points paired")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("tolerance")
+                .long("tolerance")
+                .help("Pixels a point may be from the simplified drawing and still be removed")
+                .default_value("0.5")
+                .value_parser(parse_tolerance),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Project to write [default: overwrite the project, keeping a backup]")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("project").expect("required");
+    let tolerance = *matches.get_one::<f32>("tolerance").expect("defaulted");
+    let output = matches.get_one::<PathBuf>("output").unwrap_or(path);
+
+    let mut project = Project::load(path)?;
+    let before = point_count(&project);
+    simplify(&mut project, tolerance);
+    let after = point_count(&project);
+    save_output(&project, path, output)?;
+    println!(
+        "{} points simplified to {} ({:.0}% smaller)",
+        before,
+        after,
+        if before > 0 {
+            (1.0 - after as f32 / before as f32) * 100.0
+        } else {
+            0.0
+        }
+    );
+    Ok(())
+}
+
+fn simplify(project: &mut Project, tolerance: f32) {
+    for drawing in &mut project.drawings {
+        // Keep the discs overlapping at their smallest
+        let spacing = drawing
+            .source_settings
+            .radius
+            .min(drawing.target_settings.radius);
+        (drawing.source_points, drawing.target_points) =
+            drawing
+                .source_points
+                .simplify_paired(&drawing.target_points, tolerance, spacing);
+    }
+}
+
+fn point_count(project: &Project) -> usize {
+    project
+        .drawings
+        .iter()
+        .map(|drawing| drawing.source_points.0.len() + drawing.target_points.0.len())
+        .sum()
+}
+
+fn parse_tolerance(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(tolerance) if tolerance >= 0.0 && tolerance.is_finite() => Ok(tolerance),
+        _ => Err("expected a number of pixels, 0 or more".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_simplify() {
+        let mut project =
+            Project::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("web/demo/x.terp")).unwrap();
+        let before = point_count(&project);
+        simplify(&mut project, 0.5);
+        assert!(point_count(&project) < before);
+        for drawing in &project.drawings {
+            assert_eq!(drawing.source_points.0.len(), drawing.target_points.0.len());
+        }
+
+        // Nothing is within no tolerance, except points exactly on the line
+        let mut exact =
+            Project::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("web/demo/x.terp")).unwrap();
+        simplify(&mut exact, 0.0);
+        assert!(point_count(&exact) >= point_count(&project));
+    }
+}
@@ -0,0 +1,394 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bevy::{math::Affine2, prelude::*};
+use clap::{
+    Arg, ArgAction, ArgGroup, ArgMatches, Command, ValueHint, builder::PossibleValuesParser,
+    parser::ValueSource, value_parser,
+};
+
+use super::{indexed, parse_translation, save_output};
+use crate::{
+    Interpolated,
+    points::{Points, PointsSettings},
+    project::{Drawing, Project},
+};
+
+pub(super) fn command() -> Command {
+    Command::new("transform")
+        .about("Move, scale, rotate or flip every drawing in a project")
+        .after_help(
+            "Operations apply in the order given, e.g. `terp transform project.terp --recenter \
+             --scale 1.5,1 --translate 0,-40` centers the drawings, stretches them and moves them down.\n\
+             Point radius is scaled with the drawings, by the square root of the area scale.",
+        )
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Project to write [default: overwrite the project, keeping a backup]")
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("side")
+                .long("side")
+                .help("Side of the drawings to transform")
+                .default_value("both")
+                .value_parser(PossibleValuesParser::new(["source", "target", "both"])),
+        )
+        .arg(
+            Arg::new("translate")
+                .long("translate")
+                .help("Move by X,Y pixels, y up")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .value_parser(parse_translation),
+        )
+        .arg(
+            Arg::new("scale")
+                .long("scale")
+                .help("Scale about the canvas center, by S or SX,SY")
+                .action(ArgAction::Append)
+                .value_parser(parse_scale),
+        )
+        .arg(
+            Arg::new("rotate")
+                .long("rotate")
+                .help("Rotate counterclockwise about the canvas center, in degrees")
+                .action(ArgAction::Append)
+                .allow_negative_numbers(true)
+                .value_parser(parse_degrees),
+        )
+        .arg(
+            Arg::new("flip")
+                .long("flip")
+                .help("Mirror across the canvas center")
+                .action(ArgAction::Append)
+                .value_parser(PossibleValuesParser::new(["horizontal", "vertical"])),
+        )
+        .arg(
+            Arg::new("recenter")
+                .long("recenter")
+                .help("Move the drawings' bounds to the canvas center")
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("operation")
+                .args(["translate", "scale", "rotate", "flip", "recenter"])
+                .multiple(true)
+                .required(true),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("project").expect("required");
+    let output = matches.get_one::<PathBuf>("output").unwrap_or(path);
+    let sides: &[Interpolated] = match matches
+        .get_one::<String>("side")
+        .expect("defaulted")
+        .as_str()
+    {
+        "source" => &[Interpolated::Source],
+        "target" => &[Interpolated::Target],
+        "both" => &[Interpolated::Source, Interpolated::Target],
+        _ => unreachable!("Validated by clap"),
+    };
+
+    let mut project = Project::load(path)?;
+    for operation in operations(matches) {
+        transform(&mut project, sides, operation)?;
+    }
+    save_output(&project, path, output)?;
+
+    println!("Transformed {} drawings", project.drawings.len());
+    if let Some(bounds) = project.bounds() {
+        let canvas = project.settings.canvas_rect();
+        if !(canvas.contains(bounds.min) && canvas.contains(bounds.max)) {
+            println!(
+                "The drawings extend outside the {}x{} canvas, \
+                 `terp settings --canvas` can resize it",
+                canvas.width(),
+                canvas.height()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Operation {
+    Translate(Vec2),
+    Scale(Vec2),
+    /// Counterclockwise degrees
+    Rotate(f32),
+    /// Mirror left to right (horizontal) or top to bottom (vertical)
+    Flip(BVec2),
+    Recenter,
+}
+
+// Operations in command line order
+fn operations(matches: &ArgMatches) -> Vec<Operation> {
+    let mut operations: Vec<(usize, Operation)> = Vec::new();
+    operations.extend(
+        indexed(matches, "translate")
+            .into_iter()
+            .map(|(index, translation)| (index, Operation::Translate(translation))),
+    );
+    operations.extend(
+        indexed(matches, "scale")
+            .into_iter()
+            .map(|(index, scale)| (index, Operation::Scale(scale))),
+    );
+    operations.extend(
+        indexed(matches, "rotate")
+            .into_iter()
+            .map(|(index, degrees)| (index, Operation::Rotate(degrees))),
+    );
+    operations.extend(
+        indexed::<String>(matches, "flip")
+            .into_iter()
+            .map(|(index, axis)| {
+                let horizontal = axis == "horizontal";
+                (index, Operation::Flip(BVec2::new(horizontal, !horizontal)))
+            }),
+    );
+    // The flag defaults to false when it isn't given
+    if matches.value_source("recenter") == Some(ValueSource::CommandLine) {
+        operations.extend(
+            matches
+                .indices_of("recenter")
+                .into_iter()
+                .flatten()
+                .map(|index| (index, Operation::Recenter)),
+        );
+    }
+    operations.sort_by_key(|(index, _)| *index);
+    operations
+        .into_iter()
+        .map(|(_, operation)| operation)
+        .collect()
+}
+
+fn transform(project: &mut Project, sides: &[Interpolated], operation: Operation) -> Result<()> {
+    let affine = match operation {
+        Operation::Translate(translation) => Affine2::from_translation(translation),
+        Operation::Scale(scale) => Affine2::from_scale(scale),
+        Operation::Rotate(degrees) => Affine2::from_angle(degrees.to_radians()),
+        Operation::Flip(axes) => Affine2::from_scale(Vec2::select(axes, Vec2::NEG_ONE, Vec2::ONE)),
+        Operation::Recenter => {
+            let bounds = project
+                .drawings
+                .iter()
+                .flat_map(|drawing| {
+                    sides.iter().filter_map(|side| {
+                        let (points, settings) = side_of(drawing, *side);
+                        points.bounds().map(|rect| rect.inflate(settings.radius))
+                    })
+                })
+                .reduce(|a, b| a.union(b))
+                .context("Nothing to recenter, the project has no points")?;
+            Affine2::from_translation(-bounds.center())
+        }
+    };
+    // Discs keep covering the same share of the drawings
+    let radius_scale = affine.matrix2.determinant().abs().sqrt();
+
+    for drawing in &mut project.drawings {
+        for side in sides {
+            let (points, settings) = side_of_mut(drawing, *side);
+            for point in &mut points.0 {
+                *point = affine.transform_point2(*point);
+            }
+            settings.radius *= radius_scale;
+        }
+    }
+    Ok(())
+}
+
+fn side_of(drawing: &Drawing, side: Interpolated) -> (&Points, &PointsSettings) {
+    match side {
+        Interpolated::Source => (&drawing.source_points, &drawing.source_settings),
+        Interpolated::Target => (&drawing.target_points, &drawing.target_settings),
+    }
+}
+
+fn side_of_mut(drawing: &mut Drawing, side: Interpolated) -> (&mut Points, &mut PointsSettings) {
+    match side {
+        Interpolated::Source => (&mut drawing.source_points, &mut drawing.source_settings),
+        Interpolated::Target => (&mut drawing.target_points, &mut drawing.target_settings),
+    }
+}
+
+fn parse_scale(value: &str) -> Result<Vec2, String> {
+    let scale = match value.split_once(',') {
+        Some((x, y)) => x.parse().ok().zip(y.parse().ok()).map(Vec2::from),
+        None => value.parse().ok().map(Vec2::splat),
+    };
+    match scale {
+        Some(scale) if scale.cmpgt(Vec2::ZERO).all() && scale.is_finite() => Ok(scale),
+        _ => Err("expected a positive scale S, or SX,SY (e.g. 1.5,1)".into()),
+    }
+}
+
+fn parse_degrees(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(degrees) if degrees.is_finite() => Ok(degrees),
+        _ => Err("expected a number of degrees".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOTH: &[Interpolated] = &[Interpolated::Source, Interpolated::Target];
+
+    fn project() -> Project {
+        let settings = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 2.0,
+        };
+        Project {
+            drawings: vec![Drawing {
+                source_settings: settings,
+                target_settings: settings,
+                source_points: Points(vec![Vec2::new(10.0, 0.0), Vec2::new(20.0, 10.0)]),
+                target_points: Points(vec![Vec2::new(-10.0, 0.0), Vec2::new(0.0, 10.0)]),
+                layer: 1.0,
+            }],
+            ..default()
+        }
+    }
+
+    fn assert_points(points: &Points, expected: &[Vec2]) {
+        assert_eq!(points.0.len(), expected.len());
+        for (point, expected) in points.0.iter().zip(expected) {
+            assert!(
+                point.abs_diff_eq(*expected, 1e-4),
+                "{} != {}",
+                point,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_operations() {
+        let matches = command()
+            .try_get_matches_from([
+                "transform",
+                "project.terp",
+                "--scale",
+                "2",
+                "--recenter",
+                "--translate",
+                "-5,5",
+                "--flip",
+                "vertical",
+                "--rotate",
+                "-90",
+                "--scale",
+                "1.5,1",
+            ])
+            .unwrap();
+        assert_eq!(
+            operations(&matches),
+            vec![
+                Operation::Scale(Vec2::splat(2.0)),
+                Operation::Recenter,
+                Operation::Translate(Vec2::new(-5.0, 5.0)),
+                Operation::Flip(BVec2::new(false, true)),
+                Operation::Rotate(-90.0),
+                Operation::Scale(Vec2::new(1.5, 1.0)),
+            ]
+        );
+
+        let matches = command()
+            .try_get_matches_from(["transform", "project.terp", "--rotate", "45"])
+            .unwrap();
+        assert_eq!(operations(&matches), vec![Operation::Rotate(45.0)]);
+        assert!(
+            command()
+                .try_get_matches_from(["transform", "project.terp"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_run_backup() {
+        let directory = std::env::temp_dir().join(format!("terp-transform-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("x.terp");
+        let output = directory.join("y.terp");
+        project().save(&path).unwrap();
+        let (path, output) = (path.to_str().unwrap(), output.to_str().unwrap());
+        let run_with = |args: &[&str]| {
+            let matches = command()
+                .try_get_matches_from(["transform", "--translate", "1,1"].iter().chain(args))
+                .unwrap();
+            run(&matches).unwrap();
+        };
+
+        // Overwriting the project backs it up
+        run_with(&[path]);
+        assert!(directory.join("x.terp.1.bak").exists());
+
+        // Other outputs are not backed up
+        run_with(&[path, "-o", output]);
+        run_with(&[path, "-o", output]);
+        assert!(directory.join("y.terp").exists());
+        assert!(!directory.join("y.terp.1.bak").exists());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut scaled = project();
+        transform(&mut scaled, BOTH, Operation::Scale(Vec2::new(2.0, 0.5))).unwrap();
+        let drawing = &scaled.drawings[0];
+        assert_points(
+            &drawing.source_points,
+            &[Vec2::new(20.0, 0.0), Vec2::new(40.0, 5.0)],
+        );
+        // The area is unchanged
+        assert_eq!(drawing.source_settings.radius, 2.0);
+
+        let mut rotated = project();
+        transform(&mut rotated, BOTH, Operation::Rotate(90.0)).unwrap();
+        transform(&mut rotated, BOTH, Operation::Flip(BVec2::new(true, false))).unwrap();
+        assert_points(
+            &rotated.drawings[0].target_points,
+            &[Vec2::new(0.0, -10.0), Vec2::new(10.0, 0.0)],
+        );
+
+        // Only the source moves, and doubles in size
+        let mut source = project();
+        transform(
+            &mut source,
+            &[Interpolated::Source],
+            Operation::Scale(Vec2::splat(2.0)),
+        )
+        .unwrap();
+        transform(&mut source, &[Interpolated::Source], Operation::Recenter).unwrap();
+        let drawing = &source.drawings[0];
+        assert_points(
+            &drawing.source_points,
+            &[Vec2::new(-10.0, -10.0), Vec2::new(10.0, 10.0)],
+        );
+        assert_eq!(drawing.source_settings.radius, 4.0);
+        assert_points(
+            &drawing.target_points,
+            &[Vec2::new(-10.0, 0.0), Vec2::new(0.0, 10.0)],
+        );
+        assert_eq!(drawing.target_settings.radius, 2.0);
+
+        assert!(transform(&mut Project::default(), BOTH, Operation::Recenter).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::{fmt, path::PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command, ValueHint, value_parser};
+
+use crate::{
+    points::{Points, PointsSettings},
+    project::Project,
+};
+
+pub(super) fn command() -> Command {
+    Command::new("validate")
+        .about("Check projects for problems, exiting with an error if any are found")
+        .arg(
+            Arg::new("project")
+                .required(true)
+                .num_args(1..)
+                .value_hint(ValueHint::FilePath)
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub(super) fn run(matches: &ArgMatches) -> Result<()> {
+    let mut count = 0;
+    for path in matches.get_many::<PathBuf>("project").expect("required") {
+        let problems = match Project::load(path) {
+            Ok(project) => problems(&project),
+            Err(err) => vec![Problem::project(format!("{:#}", err))],
+        };
+        if problems.is_empty() {
+            println!("{}: ok", path.display());
+        }
+        for problem in &problems {
+            println!("{}: {}", path.display(), problem);
+        }
+        count += problems.len();
+    }
+    if count > 0 {
+        bail!(
+            "{} problem{} found",
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct Problem {
+    /// Index of the drawing in the project, or none for the whole project
+    drawing: Option<usize>,
+    message: String,
+}
+
+impl Problem {
+    fn project(message: String) -> Self {
+        Self {
+            drawing: None,
+            message,
+        }
+    }
+
+    fn drawing(index: usize, message: String) -> Self {
+        Self {
+            drawing: Some(index),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.drawing {
+            Some(index) => write!(f, "drawing {}: {}", index, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+fn problems(project: &Project) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let settings = &project.settings;
+    problems.extend(settings.problems().into_iter().map(Problem::project));
+
+    let canvas = settings.canvas_rect();
+    for (index, drawing) in project.drawings.iter().enumerate() {
+        let mut problem = |message| problems.push(Problem::drawing(index, message));
+        let (source, target) = (drawing.source_points.0.len(), drawing.target_points.0.len());
+        if source != target {
+            problem(format!(
+                "{} source points but {} target points, they can't be interpolated",
+                source, target
+            ));
+        }
+        let sides = [
+            ("source", &drawing.source_points, &drawing.source_settings),
+            ("target", &drawing.target_points, &drawing.target_settings),
+        ];
+        for (side, points, settings) in sides {
+            side_problems(side, points, settings, &mut problem);
+        }
+        if !drawing.layer.is_finite() {
+            problem(format!("layer {} is not a number", drawing.layer));
+        } else if let Some(other) = project.drawings[..index]
+            .iter()
+            .position(|other| other.layer == drawing.layer)
+        {
+            problem(format!(
+                "layer {} is also used by drawing {}",
+                drawing.layer, other
+            ));
+        }
+        // Bounds are meaningless if a side has problems of its own
+        let finite = sides.iter().all(|(_, points, settings)| {
+            settings.radius >= 0.0 && points.0.iter().all(|point| point.is_finite())
+        });
+        let outside = drawing.bounds().filter(|bounds| {
+            finite && !(canvas.contains(bounds.min) && canvas.contains(bounds.max))
+        });
+        if let Some(bounds) = outside {
+            problem(format!(
+                "extends outside the canvas, its bounds are ({}, {}) to ({}, {})",
+                bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y
+            ));
+        }
+    }
+    problems
+}
+
+fn side_problems(
+    side: &str,
+    points: &Points,
+    settings: &PointsSettings,
+    problem: &mut impl FnMut(String),
+) {
+    if points.0.is_empty() {
+        problem(format!("{} stroke is empty", side));
+    }
+    if let Some(point) = points.0.iter().position(|point| !point.is_finite()) {
+        let count = points.0.iter().filter(|point| !point.is_finite()).count();
+        problem(format!(
+            "{} has {} invalid coordinate{}, first at point {}",
+            side,
+            count,
+            if count == 1 { "" } else { "s" },
+            point
+        ));
+    }
+    if !(settings.radius >= 0.0 && settings.radius.is_finite()) {
+        problem(format!("{} radius {} is invalid", side, settings.radius));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::Drawing;
+    use bevy::prelude::*;
+
+    fn drawing(layer: f32, source: Vec<Vec2>, target: Vec<Vec2>) -> Drawing {
+        let settings = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 2.0,
+        };
+        Drawing {
+            source_settings: settings,
+            target_settings: settings,
+            source_points: Points(source),
+            target_points: Points(target),
+            layer,
+        }
+    }
+
+    #[test]
+    fn test_valid() {
+        let project = Project {
+            drawings: vec![
+                drawing(1.0, vec![Vec2::ZERO], vec![Vec2::ONE]),
+                drawing(2.0, vec![Vec2::ONE], vec![Vec2::ZERO]),
+            ],
+            ..default()
+        };
+        assert_eq!(problems(&project), vec![]);
+        assert_eq!(problems(&Project::default()), vec![]);
+    }
+
+    #[test]
+    fn test_problems() {
+        let mut negative = drawing(4.0, vec![Vec2::ZERO], vec![Vec2::ZERO]);
+        negative.target_settings.radius = -1.0;
+        let project = Project {
+            drawings: vec![
+                drawing(1.0, vec![Vec2::ZERO, Vec2::ONE], vec![Vec2::ONE]),
+                drawing(2.0, vec![], vec![]),
+                drawing(1.0, vec![Vec2::NAN, Vec2::ZERO], vec![Vec2::ZERO, Vec2::X]),
+                drawing(3.0, vec![Vec2::new(500.0, 0.0)], vec![Vec2::ZERO]),
+                negative,
+            ],
+            ..default()
+        };
+        let messages: Vec<String> = problems(&project).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "drawing 0: 2 source points but 1 target points, they can't be interpolated",
+                "drawing 1: source stroke is empty",
+                "drawing 1: target stroke is empty",
+                "drawing 2: source has 1 invalid coordinate, first at point 0",
+                "drawing 2: layer 1 is also used by drawing 0",
+                "drawing 3: extends outside the canvas, its bounds are (-2, -2) to (502, 2)",
+                "drawing 4: target radius -1 is invalid",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_settings_problems() {
+        let mut project = Project::default();
+        project.settings.canvas = Vec2::new(0.0, 100.0);
+        project.settings.duration = f32::NAN;
+        let problems = problems(&project);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|problem| problem.drawing.is_none()));
+    }
+}
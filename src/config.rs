@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io, path::PathBuf};
+
+use anyhow::Result;
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::{Context, bail};
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, de};
+
+use crate::{
+    animation,
+    draw::Brush,
+    keymap::Bindings,
+    project::{Project, ProjectSettings},
+};
+
+/// User preferences, read from `config.toml` in the user's config directory when the app starts.
+/// Missing settings keep their defaults.
+#[derive(Resource, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) brush: BrushConfig,
+    pub(crate) window: WindowConfig,
+    /// Settings of new projects
+    pub(crate) animation: AnimationConfig,
+    /// Bindings by command name, replacing the command's default bindings
+    pub(crate) keys: HashMap<String, Bindings>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BrushConfig {
+    pub(crate) radius: f32,
+    #[serde(deserialize_with = "color")]
+    pub(crate) color: Srgba,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        let brush = Brush::default();
+        Self {
+            radius: brush.radius,
+            color: brush.color.into(),
+        }
+    }
+}
+
+/// Window sizes in logical pixels
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct WindowConfig {
+    pub(crate) editor: Vec2,
+    pub(crate) player: Vec2,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            editor: Vec2::new(1200.0, 600.0),
+            player: Vec2::new(600.0, 600.0),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AnimationConfig {
+    pub(crate) duration: f32,
+    pub(crate) easing: EaseFunction,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            duration: animation::DURATION,
+            easing: animation::EASING,
+        }
+    }
+}
+
+impl Config {
+    /// Read the user's config, or the defaults if there is none
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn load() -> Result<Self> {
+        let Some(path) = path() else {
+            return Ok(default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                Self::from_toml(&text).with_context(|| format!("Invalid {}", path.display()))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(default()),
+            Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_toml(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check(&self) -> Result<()> {
+        if !(self.brush.radius > 0.0 && self.brush.radius.is_finite()) {
+            bail!("brush radius {} is not positive", self.brush.radius);
+        }
+        for (name, size) in [
+            ("editor", self.window.editor),
+            ("player", self.window.player),
+        ] {
+            if !(size.cmpgt(Vec2::ZERO).all() && size.is_finite()) {
+                bail!("{} window size {}x{} is not positive", name, size.x, size.y);
+            }
+        }
+        if !(self.animation.duration > 0.0 && self.animation.duration.is_finite()) {
+            bail!(
+                "animation duration {} is not positive",
+                self.animation.duration
+            );
+        }
+        Ok(())
+    }
+
+    /// Brush to start drawing with
+    pub(crate) fn brush(&self) -> Brush {
+        Brush {
+            radius: self.brush.radius,
+            color: self.brush.color.into(),
+        }
+    }
+
+    pub(crate) fn project_settings(&self) -> ProjectSettings {
+        ProjectSettings {
+            duration: self.animation.duration,
+            easing: self.animation.easing,
+            ..default()
+        }
+    }
+
+    /// An empty project with the configured settings
+    pub(crate) fn new_project(&self) -> Project {
+        Project {
+            settings: self.project_settings(),
+            ..default()
+        }
+    }
+}
+
+/// Where the config is read from, e.g. `~/.config/terp/config.toml` on Linux
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|directory| directory.join("terp").join("config.toml"))
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Srgba, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Srgba::hex(&hex).map_err(|_| de::Error::custom(format!("invalid color \"{}\"", hex)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+
+        let config = Config::from_toml(
+            r##"
+            [brush]
+            radius = 4
+            color = "#ff8800"
+
+            [window]
+            editor = [1600, 800]
+
+            [animation]
+            easing = "SineInOut"
+
+            [keys]
+            play = "p"
+            save = ["Ctrl+S", "F2"]
+            undo = []
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.brush.radius, 4.0);
+        assert_eq!(config.brush().color, Srgba::hex("#ff8800").unwrap().into());
+        assert_eq!(config.window.editor, Vec2::new(1600.0, 800.0));
+        assert_eq!(config.window.player, WindowConfig::default().player);
+        let settings = config.new_project().settings;
+        assert_eq!(settings.easing, EaseFunction::SineInOut);
+        assert_eq!(settings.duration, animation::DURATION);
+        assert_eq!(config.keys["play"].0, vec!["P".parse().unwrap()]);
+        assert_eq!(config.keys["save"].0.len(), 2);
+        assert_eq!(config.keys["undo"], Bindings(vec![]));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for invalid in [
+            "[brush]\ncolor = \"orange\"",
+            "[brush]\nradius = -1",
+            "[window]\nplayer = [0, 600]",
+            "[animation]\nduration = 0",
+            "[keys]\nplay = \"Hyper\"",
+            "[keys]\nsave = [\"Ctrl+S\", \"Ctrl+Alt\"]",
+            "[brushes]",
+        ] {
+            assert!(Config::from_toml(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -3,21 +3,27 @@ use crate::{
     animation::Animatable,
     camera::{SOURCE_LAYER, TARGET_LAYER},
     error_handler,
+    import::svg,
+    keymap::{RegisterCommand, command_just_pressed},
     points::{Points, PointsMaterial, PointsMeshBuilder, PointsSettings},
-    project::LoadProject,
+    project::{ImportSvg, LoadProject},
     util::window_position_to_world,
 };
 use anyhow::Result;
 use bevy::{
-    ecs::query::{QueryData, QueryEntityError},
+    ecs::{
+        entity::EntityHashSet,
+        query::{QueryData, QueryEntityError},
+    },
     prelude::*,
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.insert_resource(Brush::default())
+    app.init_resource::<Brush>()
         .insert_resource(Undo::default())
         .insert_resource(DrawingCount::default())
         .add_event::<UndoEvent>()
+        .register_command("undo", "Undo the last drawing", &["Ctrl+Z"])
         .add_systems(OnEnter(AppState::Draw(Interpolated::Source)), start_drawing)
         .add_systems(OnEnter(AppState::Draw(Interpolated::Target)), start_drawing)
         .add_systems(OnExit(AppState::Draw(Interpolated::Source)), end_drawing)
@@ -26,8 +32,17 @@ pub(super) fn plugin(app: &mut App) {
         .add_systems(
             Update,
             (
-                load_project.pipe(error_handler),
-                undo_drawing.run_if(in_state(AppState::Idle)),
+                (
+                    load_project.pipe(error_handler),
+                    import_svg.pipe(error_handler),
+                    merge_drawings,
+                )
+                    .chain(),
+                (
+                    send_undo.run_if(command_just_pressed("undo")),
+                    undo_drawing.run_if(in_state(AppState::Idle)),
+                )
+                    .chain(),
             ),
         );
 }
@@ -108,6 +123,10 @@ struct MergedDrawing(Entity);
 #[derive(Component)]
 struct DrawingNumber(usize);
 
+/// Finished drawing waiting to be paired with the other side
+#[derive(Component)]
+struct PendingMerge;
+
 fn draw_condition(state: Res<State<AppState>>, buttons: Res<ButtonInput<MouseButton>>) -> bool {
     match state.get() {
         AppState::Draw(_) => buttons.pressed(MouseButton::Left),
@@ -130,7 +149,36 @@ fn start_drawing(
         _ => return,
     };
 
-    let (count, render_layers) = match interpolation_type {
+    let settings = PointsSettings {
+        color: brush.color.into(),
+        radius: brush.radius,
+    };
+    let entity = spawn_drawing(
+        &mut commands,
+        &mut drawing_count,
+        *interpolation_type,
+        meshes.add(Mesh::build(None)),
+        materials.add(PointsMaterial {
+            source_settings: settings,
+            target_settings: settings,
+            t: 0.0,
+        }),
+    )
+    .insert(ActiveDrawing)
+    .id();
+
+    undo.add(entity);
+}
+
+// Spawn the next numbered drawing on one side
+fn spawn_drawing<'a>(
+    commands: &'a mut Commands,
+    drawing_count: &mut DrawingCount,
+    interpolated: Interpolated,
+    mesh: Handle<Mesh>,
+    material: Handle<PointsMaterial>,
+) -> EntityCommands<'a> {
+    let (count, render_layers) = match interpolated {
         Interpolated::Source => {
             drawing_count.source += 1;
             (drawing_count.source, SOURCE_LAYER)
@@ -142,29 +190,14 @@ fn start_drawing(
         }
     };
 
-    let entity = commands
-        .spawn((
-            ActiveDrawing,
-            DrawingNumber(count),
-            render_layers,
-            *interpolation_type,
-            Mesh2d(meshes.add(Mesh::build(None))),
-            Transform::from_xyz(0., 0., count as f32), // use count as Z index
-            MeshMaterial2d(materials.add(PointsMaterial {
-                source_settings: PointsSettings {
-                    color: brush.color.into(),
-                    radius: brush.radius,
-                },
-                target_settings: PointsSettings {
-                    color: brush.color.into(),
-                    radius: brush.radius,
-                },
-                t: 0.0,
-            })),
-        ))
-        .id();
-
-    undo.add(entity);
+    commands.spawn((
+        DrawingNumber(count),
+        render_layers,
+        interpolated,
+        Mesh2d(mesh),
+        Transform::from_xyz(0., 0., count as f32), // use count as Z index
+        MeshMaterial2d(material),
+    ))
 }
 
 #[derive(QueryData)]
@@ -174,32 +207,46 @@ struct DrawingQuery {
     interpolation: &'static Interpolated,
 }
 
-fn end_drawing(
+fn end_drawing(mut commands: Commands, active_drawing: Single<Entity, With<ActiveDrawing>>) {
+    commands
+        .entity(*active_drawing)
+        .remove::<ActiveDrawing>()
+        .insert(PendingMerge);
+}
+
+fn merge_drawings(
     mut commands: Commands,
-    active_drawing: Single<DrawingQuery, With<ActiveDrawing>>,
+    pending_drawings: Query<DrawingQuery, With<PendingMerge>>,
     unmerged_drawings: Query<DrawingQuery, (Without<ActiveDrawing>, Without<MergedDrawing>)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut points_materials: ResMut<Assets<PointsMaterial>>,
     mesh_query: Query<(&Mesh2d, &MeshMaterial2d<PointsMaterial>), Without<MergedDrawing>>,
 ) {
-    let active_drawing = active_drawing.into_inner();
-    commands
-        .entity(active_drawing.entity)
-        .remove::<ActiveDrawing>();
-    // Try to find a drawing of the opposite interpolation with the same number
-    for unmerged_drawing in &unmerged_drawings {
-        if unmerged_drawing.number.0 == active_drawing.number.0
-            && unmerged_drawing.interpolation != active_drawing.interpolation
-        {
-            let (source_entity, target_entity) = match *active_drawing.interpolation {
-                Interpolated::Source => (active_drawing.entity, unmerged_drawing.entity),
-                Interpolated::Target => (unmerged_drawing.entity, active_drawing.entity),
-            };
-
-            let mut process_mesh_material = |result: Result<
-                (&Mesh2d, &MeshMaterial2d<PointsMaterial>),
-                QueryEntityError<'_>,
-            >| {
+    // Merges are deferred, so track drawings merged in this pass
+    let mut merged = EntityHashSet::default();
+    for pending_drawing in &pending_drawings {
+        commands
+            .entity(pending_drawing.entity)
+            .remove::<PendingMerge>();
+        if merged.contains(&pending_drawing.entity) {
+            continue;
+        }
+        // Try to find a drawing of the opposite interpolation with the same number
+        let Some(unmerged_drawing) = unmerged_drawings.iter().find(|unmerged_drawing| {
+            unmerged_drawing.number.0 == pending_drawing.number.0
+                && unmerged_drawing.interpolation != pending_drawing.interpolation
+                && !merged.contains(&unmerged_drawing.entity)
+        }) else {
+            continue;
+        };
+
+        let (source_entity, target_entity) = match *pending_drawing.interpolation {
+            Interpolated::Source => (pending_drawing.entity, unmerged_drawing.entity),
+            Interpolated::Target => (unmerged_drawing.entity, pending_drawing.entity),
+        };
+
+        let mut process_mesh_material =
+            |result: Result<(&Mesh2d, &MeshMaterial2d<PointsMaterial>), QueryEntityError<'_>>| {
                 result.ok().and_then(|(mesh2d, material2d)| {
                     meshes.remove(mesh2d).and_then(|mesh| {
                         points_materials
@@ -209,37 +256,38 @@ fn end_drawing(
                 })
             };
 
-            let Some((mut source_mesh, mut source_material)) =
-                process_mesh_material(mesh_query.get(source_entity))
-            else {
-                return;
-            };
-            let Some((target_mesh, mut target_material)) =
-                process_mesh_material(mesh_query.get(target_entity))
-            else {
-                return;
-            };
-
-            Points::interpolate(&mut source_mesh, &target_mesh);
-            let mesh_handle = meshes.add(source_mesh);
-
-            source_material.target_settings = target_material.source_settings;
-            source_material.t = 0.0;
-            target_material = source_material;
-            target_material.t = 1.0;
-
-            commands.entity(target_entity).insert((
-                MergedDrawing(source_entity),
-                Mesh2d(mesh_handle.clone()),
-                MeshMaterial2d(points_materials.add(target_material)),
-            ));
-            commands.entity(source_entity).insert((
-                Animatable,
-                MergedDrawing(target_entity),
-                Mesh2d(mesh_handle),
-                MeshMaterial2d(points_materials.add(source_material)),
-            ));
-        }
+        let Some((mut source_mesh, mut source_material)) =
+            process_mesh_material(mesh_query.get(source_entity))
+        else {
+            continue;
+        };
+        let Some((target_mesh, mut target_material)) =
+            process_mesh_material(mesh_query.get(target_entity))
+        else {
+            continue;
+        };
+
+        Points::interpolate(&mut source_mesh, &target_mesh);
+        let mesh_handle = meshes.add(source_mesh);
+
+        source_material.target_settings = target_material.source_settings;
+        source_material.t = 0.0;
+        target_material = source_material;
+        target_material.t = 1.0;
+
+        commands.entity(target_entity).insert((
+            MergedDrawing(source_entity),
+            Mesh2d(mesh_handle.clone()),
+            MeshMaterial2d(points_materials.add(target_material)),
+        ));
+        commands.entity(source_entity).insert((
+            Animatable,
+            MergedDrawing(target_entity),
+            Mesh2d(mesh_handle),
+            MeshMaterial2d(points_materials.add(source_material)),
+        ));
+        merged.insert(source_entity);
+        merged.insert(target_entity);
     }
 }
 
@@ -273,6 +321,10 @@ fn draw(
     }
 }
 
+fn send_undo(mut commands: Commands) {
+    commands.send_event(UndoEvent);
+}
+
 fn undo_drawing(
     mut commands: Commands,
     mut undo: ResMut<Undo>,
@@ -298,6 +350,40 @@ fn undo_drawing(
     }
 }
 
+fn import_svg(
+    mut events: EventReader<ImportSvg>,
+    mut commands: Commands,
+    mut drawing_count: ResMut<DrawingCount>,
+    brush: Res<Brush>,
+    mut undo: ResMut<Undo>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PointsMaterial>>,
+) -> Result<()> {
+    let default = PointsSettings {
+        color: brush.color.into(),
+        radius: brush.radius,
+    };
+    for event in events.read() {
+        for stroke in svg::strokes(&event.data, default)? {
+            let entity = spawn_drawing(
+                &mut commands,
+                &mut drawing_count,
+                event.interpolated,
+                meshes.add(Mesh::build(Some(&stroke.points))),
+                materials.add(PointsMaterial {
+                    source_settings: stroke.settings,
+                    target_settings: stroke.settings,
+                    t: 0.0,
+                }),
+            )
+            .insert(PendingMerge)
+            .id();
+            undo.add(entity);
+        }
+    }
+    Ok(())
+}
+
 fn load_project(
     mut events: EventReader<LoadProject>,
     mut commands: Commands,
@@ -369,3 +455,71 @@ fn load_project(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_svg() {
+        let mut app = App::new();
+        app.init_resource::<Assets<PointsMaterial>>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Brush>()
+            .init_resource::<Undo>()
+            .init_resource::<DrawingCount>()
+            .add_event::<ImportSvg>()
+            .add_systems(
+                Update,
+                (import_svg.pipe(error_handler), merge_drawings).chain(),
+            );
+
+        let svg = |d: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"><path d="{}" stroke="red"/></svg>"#,
+                d
+            )
+            .into_bytes()
+        };
+        app.world_mut().send_event(ImportSvg {
+            interpolated: Interpolated::Source,
+            data: svg("M0 0 L10 0 M0 10 L10 10"),
+        });
+        app.world_mut().send_event(ImportSvg {
+            interpolated: Interpolated::Target,
+            data: svg("M0 0 L0 20"),
+        });
+        app.update();
+
+        let drawing_count = app.world().resource::<DrawingCount>();
+        assert_eq!(drawing_count.source, 2);
+        assert_eq!(drawing_count.target, 1);
+        assert_eq!(app.world().resource::<Undo>().entities.len(), 3);
+
+        let mut query = app
+            .world_mut()
+            .query::<(&Interpolated, &DrawingNumber, Option<&MergedDrawing>)>();
+        let mut drawings: Vec<_> = query
+            .iter(app.world())
+            .map(|(interpolated, number, merged)| (*interpolated, number.0, merged.is_some()))
+            .collect();
+        drawings.sort_by_key(|(interpolated, number, _)| {
+            (*number, *interpolated == Interpolated::Target)
+        });
+        assert_eq!(
+            drawings,
+            vec![
+                (Interpolated::Source, 1, true),
+                (Interpolated::Target, 1, true),
+                (Interpolated::Source, 2, false),
+            ]
+        );
+        assert_eq!(
+            app.world_mut()
+                .query_filtered::<(), With<PendingMerge>>()
+                .iter(app.world())
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use std::{ffi::OsStr, fs, path::Path};
+
+use anyhow::{Context, Result};
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use image::ImageFormat;
+
+use crate::{
+    Interpolated,
+    camera::{SOURCE_LAYER, TARGET_LAYER},
+    cli, error_handler,
+    files::OpenProject,
+    project::{Format, ImportSvg, LoadProject, Project, ProjectSettings},
+};
+
+/// Tracing images are faded so drawings stand out over them
+const TRACING_ALPHA: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, (request_dropped_project, import_dropped_file));
+}
+
+pub(super) fn player_plugin(app: &mut App) {
+    app.add_systems(Update, open_dropped_project);
+}
+
+/// Image dropped on one side of the editor to trace over, it is not saved with the project
+#[derive(Component)]
+struct TracingImage(Interpolated);
+
+#[derive(Debug, PartialEq)]
+enum Dropped {
+    Project,
+    Svg,
+    Image(ImageFormat),
+}
+
+impl Dropped {
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+        match extension.as_str() {
+            "terp" | "ron" => Some(Self::Project),
+            // Any other JSON is a Lottie animation
+            "json" if Format::from_path(path) == Format::Json => Some(Self::Project),
+            "svg" => Some(Self::Svg),
+            // Only the formats enabled in Cargo.toml can be decoded
+            "png" => Some(Self::Image(ImageFormat::Png)),
+            "jpg" | "jpeg" => Some(Self::Image(ImageFormat::Jpeg)),
+            "gif" => Some(Self::Image(ImageFormat::Gif)),
+            _ => None,
+        }
+    }
+}
+
+fn dropped_files<'a>(
+    events: &'a mut EventReader<FileDragAndDrop>,
+) -> impl Iterator<Item = &'a Path> {
+    events.read().filter_map(|event| match event {
+        FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.as_path()),
+        _ => None,
+    })
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+// The player has nothing to lose, it opens dropped projects straight away
+fn open_dropped_project(
+    mut events: EventReader<FileDragAndDrop>,
+    mut args: ResMut<cli::Args>,
+    mut commands: Commands,
+) {
+    for path in dropped_files(&mut events) {
+        if Dropped::from_path(path) == Some(Dropped::Project) {
+            // Only a file that loads becomes the project, so saving never overwrites anything else
+            let result = Project::load(path).map(|project| {
+                commands.send_event(LoadProject(project));
+                args.set_project(Some(path));
+            });
+            // Report each file that fails, and carry on with the rest
+            error_handler(In(result));
+        }
+    }
+}
+
+// The editor confirms before unsaved changes are replaced
+fn request_dropped_project(mut events: EventReader<FileDragAndDrop>, mut commands: Commands) {
+    for path in dropped_files(&mut events) {
+        if Dropped::from_path(path) == Some(Dropped::Project) {
+            commands.send_event(OpenProject(path.into()));
+        }
+    }
+}
+
+fn import_dropped_file(
+    mut events: EventReader<FileDragAndDrop>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &Interpolated)>,
+    tracing_images: Query<(Entity, &TracingImage)>,
+    settings: Res<ProjectSettings>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    for path in dropped_files(&mut events) {
+        let interpolated = side_under_cursor(&windows, &cameras);
+        let result = match Dropped::from_path(path) {
+            Some(Dropped::Svg) => read(path).map(|data| {
+                commands.send_event(ImportSvg { interpolated, data });
+            }),
+            Some(Dropped::Image(format)) => load_image(path, format).map(|image| {
+                // Fit the image in the canvas
+                let size = Vec2::new(image.width() as f32, image.height() as f32);
+                let size = size * (settings.canvas / size).min_element();
+                let image = Image::new(
+                    Extent3d {
+                        width: image.width(),
+                        height: image.height(),
+                        depth_or_array_layers: 1,
+                    },
+                    TextureDimension::D2,
+                    image.into_raw(),
+                    TextureFormat::Rgba8UnormSrgb,
+                    RenderAssetUsages::RENDER_WORLD,
+                );
+
+                // Replace the side's previous tracing image
+                for (entity, tracing_image) in &tracing_images {
+                    if tracing_image.0 == interpolated {
+                        commands.entity(entity).despawn();
+                    }
+                }
+                commands.spawn((
+                    TracingImage(interpolated),
+                    Sprite {
+                        image: images.add(image),
+                        color: Color::WHITE.with_alpha(TRACING_ALPHA),
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    // Behind every drawing
+                    Transform::from_xyz(0.0, 0.0, 0.0),
+                    match interpolated {
+                        Interpolated::Source => SOURCE_LAYER,
+                        Interpolated::Target => TARGET_LAYER,
+                    },
+                ));
+            }),
+            // Projects are opened by request_dropped_project
+            Some(Dropped::Project) | None => Ok(()),
+        };
+        // Report each file that fails, and carry on with the rest
+        error_handler(In(result));
+    }
+}
+
+fn load_image(path: &Path, format: ImageFormat) -> Result<image::RgbaImage> {
+    Ok(image::load_from_memory_with_format(&read(path)?, format)
+        .with_context(|| format!("Failed to load {}", path.display()))?
+        .into_rgba8())
+}
+
+// Files are dropped on the side under the cursor, or the source if it is unknown
+fn side_under_cursor(
+    windows: &Query<&Window>,
+    cameras: &Query<(&Camera, &Interpolated)>,
+) -> Interpolated {
+    windows
+        .iter()
+        .find_map(Window::cursor_position)
+        .and_then(|position| {
+            cameras.iter().find_map(|(camera, interpolated)| {
+                camera
+                    .logical_viewport_rect()
+                    .filter(|viewport| viewport.contains(position))
+                    .map(|_| *interpolated)
+            })
+        })
+        .unwrap_or(Interpolated::Source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_dropped_from_path() {
+        assert_eq!(
+            Dropped::from_path(Path::new("smile.terp")),
+            Some(Dropped::Project)
+        );
+        assert_eq!(
+            Dropped::from_path(Path::new("smile.terp.ron")),
+            Some(Dropped::Project)
+        );
+        assert_eq!(
+            Dropped::from_path(Path::new("smile.terp.json")),
+            Some(Dropped::Project)
+        );
+        assert_eq!(Dropped::from_path(Path::new("smile.json")), None);
+        assert_eq!(
+            Dropped::from_path(Path::new("source.SVG")),
+            Some(Dropped::Svg)
+        );
+        assert_eq!(
+            Dropped::from_path(Path::new("sketch.png")),
+            Some(Dropped::Image(ImageFormat::Png))
+        );
+        assert_eq!(
+            Dropped::from_path(Path::new("photo.JPG")),
+            Some(Dropped::Image(ImageFormat::Jpeg))
+        );
+        assert_eq!(Dropped::from_path(Path::new("photo.webp")), None);
+        assert_eq!(Dropped::from_path(Path::new("scan.tiff")), None);
+        assert_eq!(Dropped::from_path(Path::new("notes.txt")), None);
+        assert_eq!(Dropped::from_path(Path::new("README")), None);
+    }
+
+    #[test]
+    fn test_open_dropped_project() {
+        let mut app = App::new();
+        app.insert_resource(cli::Args::default())
+            .add_event::<FileDragAndDrop>()
+            .add_event::<LoadProject>()
+            .add_systems(Update, open_dropped_project);
+
+        // A file that fails to load doesn't stop the rest of the drop
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("web/demo/smile.terp");
+        for path_buf in [path.with_file_name("missing.terp"), path.clone()] {
+            app.world_mut().send_event(FileDragAndDrop::DroppedFile {
+                window: Entity::PLACEHOLDER,
+                path_buf,
+            });
+        }
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<cli::Args>().project(),
+            Some(path.as_path())
+        );
+        let events = app.world().resource::<Events<LoadProject>>();
+        let mut cursor = events.get_cursor();
+        let LoadProject(project) = cursor.read(events).last().unwrap();
+        assert_eq!(
+            project.drawings.len(),
+            Project::load(&path).unwrap().drawings.len()
+        );
+    }
+
+    #[test]
+    fn test_open_dropped_non_project() {
+        let mut app = App::new();
+        app.insert_resource(cli::Args::new(Some("old.terp")))
+            .add_event::<FileDragAndDrop>()
+            .add_event::<LoadProject>()
+            .add_systems(Update, open_dropped_project);
+
+        // Named as a project, but not one
+        let path = env::temp_dir().join(format!("terp-drop-{}.terp.json", std::process::id()));
+        fs::write(&path, br#"{"v":"5.7.0","layers":[]}"#).unwrap();
+        app.world_mut().send_event(FileDragAndDrop::DroppedFile {
+            window: Entity::PLACEHOLDER,
+            path_buf: path.clone(),
+        });
+        app.update();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            app.world().resource::<cli::Args>().project(),
+            Some(Path::new("old.terp"))
+        );
+        assert!(app.world().resource::<Events<LoadProject>>().is_empty());
+    }
+
+    #[test]
+    fn test_request_dropped_project() {
+        let mut app = App::new();
+        app.add_event::<FileDragAndDrop>()
+            .add_event::<OpenProject>()
+            .add_systems(Update, request_dropped_project);
+
+        for path_buf in ["smile.terp", "source.svg"] {
+            app.world_mut().send_event(FileDragAndDrop::DroppedFile {
+                window: Entity::PLACEHOLDER,
+                path_buf: path_buf.into(),
+            });
+        }
+        app.update();
+
+        // The editor opens it once unsaved changes are confirmed
+        let events = app.world().resource::<Events<OpenProject>>();
+        let mut cursor = events.get_cursor();
+        let paths: Vec<_> = cursor.read(events).map(|event| event.0.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("smile.terp")]);
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use bevy::prelude::*;
+
+use crate::{
+    animation,
+    project::{Project, ProjectSettings},
+};
+
+pub(crate) mod gif;
+pub(crate) mod gltf;
+pub(crate) mod lottie;
+pub(crate) mod sequence;
+pub(crate) mod sprite_sheet;
+pub(crate) mod svg;
+
+/// Keyframe rate when sampling easing functions that have no bezier equivalent
+const KEYFRAMES_PER_SECOND: f32 = 30.0;
+
+/// Area exported, the project canvas as shown by the player
+fn view(project: &Project) -> Rect {
+    project.settings.canvas_rect()
+}
+
+/// Keyframes of one full animation cycle, from source to target and back
+struct Keyframes {
+    /// Seconds for the full cycle
+    duration: f32,
+    /// Time of each keyframe as a fraction of the cycle, and the interpolation at that time
+    frames: Vec<(f32, f32)>,
+    /// Bezier easing between each pair of keyframes.
+    /// If the easing has no bezier equivalent this is `None` and keyframes are
+    /// sampled closely enough to interpolate linearly.
+    splines: Option<Vec<[Vec2; 2]>>,
+}
+
+impl Keyframes {
+    fn new(duration: f32, easing: EaseFunction) -> Result<Self> {
+        // Fails for invalid durations whatever the easing
+        let curve = animation::curve(duration, easing)?;
+        let cycle = duration * 2.0;
+        if let Some([p1, p2]) = easing_bezier(easing) {
+            return Ok(Self {
+                duration: cycle,
+                frames: vec![(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)],
+                // The return trip plays the easing backwards
+                splines: Some(vec![[p1, p2], [Vec2::ONE - p2, Vec2::ONE - p1]]),
+            });
+        }
+        let count = (cycle * KEYFRAMES_PER_SECOND).ceil().max(2.0) as usize;
+        Ok(Self {
+            duration: cycle,
+            frames: (0..=count)
+                .map(|i| {
+                    let time = i as f32 / count as f32;
+                    (time, curve.sample_clamped(time * cycle))
+                })
+                .collect(),
+            splines: None,
+        })
+    }
+}
+
+/// Interpolation of each frame of `length` seconds of the looping animation, sampled `fps` times a second.
+/// A full cycle ends where it started, so the final frame is left out to loop seamlessly.
+pub(crate) fn frames(settings: &ProjectSettings, fps: f32, length: f32) -> Result<Vec<f32>> {
+    let curve = animation::curve(settings.duration, settings.easing)?;
+    let cycle = curve.domain().length();
+    let count = (length * fps).round().max(1.0) as usize;
+    Ok((0..count)
+        .map(|i| curve.sample_clamped((i as f32 / fps) % cycle))
+        .collect())
+}
+
+/// Cubic bezier control points equivalent to an easing function,
+/// for the easings that have a well known CSS `cubic-bezier()` approximation
+fn easing_bezier(easing: EaseFunction) -> Option<[Vec2; 2]> {
+    let [x1, y1, x2, y2] = match easing {
+        EaseFunction::Linear => [0.0, 0.0, 1.0, 1.0],
+        EaseFunction::QuadraticIn => [0.11, 0.0, 0.5, 0.0],
+        EaseFunction::QuadraticOut => [0.5, 1.0, 0.89, 1.0],
+        EaseFunction::QuadraticInOut => [0.45, 0.0, 0.55, 1.0],
+        EaseFunction::CubicIn => [0.32, 0.0, 0.67, 0.0],
+        EaseFunction::CubicOut => [0.33, 1.0, 0.68, 1.0],
+        EaseFunction::CubicInOut => [0.65, 0.0, 0.35, 1.0],
+        EaseFunction::QuarticIn => [0.5, 0.0, 0.75, 0.0],
+        EaseFunction::QuarticOut => [0.25, 1.0, 0.5, 1.0],
+        EaseFunction::QuarticInOut => [0.76, 0.0, 0.24, 1.0],
+        EaseFunction::QuinticIn => [0.64, 0.0, 0.78, 0.0],
+        EaseFunction::QuinticOut => [0.22, 1.0, 0.36, 1.0],
+        EaseFunction::QuinticInOut => [0.83, 0.0, 0.17, 1.0],
+        EaseFunction::SineIn => [0.12, 0.0, 0.39, 0.0],
+        EaseFunction::SineOut => [0.61, 1.0, 0.88, 1.0],
+        EaseFunction::SineInOut => [0.37, 0.0, 0.63, 1.0],
+        EaseFunction::CircularIn => [0.55, 0.0, 1.0, 0.45],
+        EaseFunction::CircularOut => [0.0, 0.55, 0.45, 1.0],
+        EaseFunction::CircularInOut => [0.85, 0.0, 0.15, 1.0],
+        EaseFunction::ExponentialIn => [0.7, 0.0, 0.84, 0.0],
+        EaseFunction::ExponentialOut => [0.16, 1.0, 0.3, 1.0],
+        EaseFunction::ExponentialInOut => [0.87, 0.0, 0.13, 1.0],
+        _ => return None,
+    };
+    Some([Vec2::new(x1, y1), Vec2::new(x2, y2)])
+}
+
+// Format a coordinate compactly, to a hundredth of a pixel
+fn number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".into(),
+        _ => text.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(-2.5), "-2.5");
+        assert_eq!(number(0.12345), "0.12");
+        assert_eq!(number(-0.001), "0");
+        assert_eq!(number(100.0), "100");
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let keyframes = Keyframes::new(2.5, EaseFunction::CubicInOut).unwrap();
+        assert_eq!(keyframes.duration, 5.0);
+        assert_eq!(keyframes.frames, vec![(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)]);
+        let splines = keyframes.splines.unwrap();
+        assert_eq!(splines[0], [Vec2::new(0.65, 0.0), Vec2::new(0.35, 1.0)]);
+        // Symmetric easing is the same in reverse
+        assert!(splines[1][0].abs_diff_eq(splines[0][0], 1e-6));
+        assert!(splines[1][1].abs_diff_eq(splines[0][1], 1e-6));
+
+        let keyframes = Keyframes::new(1.0, EaseFunction::BounceOut).unwrap();
+        assert!(keyframes.splines.is_none());
+        assert_eq!(keyframes.frames.len(), 61);
+        assert_eq!(keyframes.frames[0], (0.0, 0.0));
+        assert_eq!(keyframes.frames[30], (0.5, 1.0));
+        assert_eq!(keyframes.frames[60], (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_frames() {
+        let settings = ProjectSettings {
+            duration: 1.0,
+            easing: EaseFunction::Linear,
+            ..default()
+        };
+        assert_eq!(
+            frames(&settings, 4.0, 2.0).unwrap(),
+            vec![0.0, 0.25, 0.5, 0.75, 1.0, 0.75, 0.5, 0.25]
+        );
+        assert_eq!(frames(&settings, 0.1, 2.0).unwrap(), vec![0.0]);
+        // Longer than a cycle loops around
+        assert_eq!(
+            frames(&settings, 2.0, 3.0).unwrap(),
+            vec![0.0, 0.5, 1.0, 0.5, 0.0, 0.5]
+        );
+    }
+}
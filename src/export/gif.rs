@@ -0,0 +1,61 @@
+use anyhow::Result;
+use bevy::prelude::*;
+use image::{
+    Delay, Frame,
+    codecs::gif::{GifEncoder, Repeat},
+};
+
+use super::frames;
+use crate::{project::Project, render::Renderer};
+
+/// Color quantization speed, from 1 (best) to 30 (fastest)
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Render one full looping animation cycle, source to target and back, as an animated GIF
+pub(crate) fn animated(project: &Project, size: UVec2, fps: u32) -> Result<Vec<u8>> {
+    let renderer = Renderer::new(size);
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    let mut data = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut data, QUANTIZE_SPEED);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(
+            frames(
+                &project.settings,
+                fps as f32,
+                project.settings.duration * 2.0,
+            )?
+            .into_iter()
+            .map(|t| Frame::from_parts(renderer.render(project, t), 0, 0, delay)),
+        )?;
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{AnimationDecoder, codecs::gif::GifDecoder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_animated() {
+        let project = Project::from_bytes(include_bytes!("../../web/demo/x.terp")).unwrap();
+        let data = animated(&project, UVec2::new(40, 30), 10).unwrap();
+        assert!(data.starts_with(b"GIF89a"));
+
+        let frames = GifDecoder::new(Cursor::new(data))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(
+            frames.len(),
+            (project.settings.duration * 2.0 * 10.0) as usize
+        );
+        assert_eq!(frames[0].delay().numer_denom_ms(), (100, 1));
+        assert_eq!(frames[0].buffer().dimensions(), (40, 30));
+        // Source and target frames differ
+        assert_ne!(frames[0].buffer(), frames[frames.len() / 2].buffer());
+    }
+}
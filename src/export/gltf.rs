@@ -0,0 +1,362 @@
+use std::f32::consts::TAU;
+
+use anyhow::Result;
+use bevy::prelude::*;
+use serde_json::{Value, json};
+
+use super::{KEYFRAMES_PER_SECOND, frames};
+use crate::project::Project;
+
+/// Triangles in the disc drawn for each point
+const SEGMENTS: usize = 12;
+/// glTF units are meters, a 600 pixel player view becomes 6 meters
+const PIXELS_PER_METER: f32 = 100.0;
+/// Distance in meters between layered drawings, later drawings are nearer the viewer
+const LAYER_SPACING: f32 = 0.001;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Export the project as binary glTF (GLB).
+/// Each drawing is a mesh of one disc per point, the source is the base mesh and the
+/// target is its single morph target (as `ATTRIBUTE_TARGET_POSITION` is for `PointsMaterial`).
+/// An animation plays the morph weight through one full cycle with the player's easing.
+pub(crate) fn glb(project: &Project) -> Result<Vec<u8>> {
+    let mut builder = Builder::default();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+    for drawing in project.layered() {
+        if drawing.source_points.0.is_empty() || drawing.target_points.0.is_empty() {
+            continue;
+        }
+        let source = Discs::new(
+            &drawing.source_points.0,
+            drawing.source_settings.radius,
+            drawing.source_settings.color,
+        );
+        let target = Discs::new(
+            &drawing.target_points.0,
+            drawing.target_settings.radius,
+            drawing.target_settings.color,
+        );
+        let count = source.positions.len().min(target.positions.len());
+        let position_deltas: Vec<Vec3> = (0..count)
+            .map(|i| target.positions[i] - source.positions[i])
+            .collect();
+        let color_deltas: Vec<Vec3> = (0..count)
+            .map(|i| target.colors[i] - source.colors[i])
+            .collect();
+
+        let position = builder.vec3(&source.positions[..count], ARRAY_BUFFER, true);
+        let color = builder.vec3(&source.colors[..count], ARRAY_BUFFER, false);
+        let target_position = builder.vec3(&position_deltas, ARRAY_BUFFER, true);
+        let target_color = builder.vec3(&color_deltas, ARRAY_BUFFER, false);
+        let indices = builder.indices(&disc_indices(count / (SEGMENTS + 1)));
+
+        meshes.push(json!({
+            "primitives": [{
+                "attributes": {"POSITION": position, "COLOR_0": color},
+                "indices": indices,
+                "material": 0,
+                "targets": [{"POSITION": target_position, "COLOR_0": target_color}],
+            }],
+            "weights": [0.0],
+            "extras": {"targetNames": ["target"]},
+        }));
+        nodes.push(json!({
+            "name": format!("drawing {}", nodes.len()),
+            "mesh": meshes.len() - 1,
+            "translation": [0.0, 0.0, nodes.len() as f32 * LAYER_SPACING],
+        }));
+    }
+
+    let mut animations = Vec::new();
+    if !nodes.is_empty() {
+        // Sample the easing, glTF can only interpolate linearly between keyframes
+        let cycle = project.settings.duration * 2.0;
+        let mut weights = frames(&project.settings, KEYFRAMES_PER_SECOND, cycle)?;
+        weights.push(weights[0]);
+        let times: Vec<f32> = (0..weights.len())
+            .map(|i| i as f32 / KEYFRAMES_PER_SECOND)
+            .collect();
+        let input = builder.scalars(&times, true);
+        let output = builder.scalars(&weights, false);
+        animations.push(json!({
+            "name": "morph",
+            "samplers": [{"input": input, "output": output, "interpolation": "LINEAR"}],
+            "channels": (0..nodes.len())
+                .map(|node| json!({"sampler": 0, "target": {"node": node, "path": "weights"}}))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    let mut gltf = json!({
+        "asset": {
+            "version": "2.0",
+            "generator": concat!(clap::crate_name!(), " ", clap::crate_version!()),
+        },
+        "extensionsUsed": ["KHR_materials_unlit"],
+        "scene": 0,
+        "materials": [{
+            "name": "points",
+            "pbrMetallicRoughness": {"baseColorFactor": [1.0, 1.0, 1.0, 1.0]},
+            "doubleSided": true,
+            "extensions": {"KHR_materials_unlit": {}},
+        }],
+    });
+    // glTF doesn't allow empty arrays, an empty project is an empty scene
+    if nodes.is_empty() {
+        gltf["scenes"] = json!([{}]);
+    } else {
+        gltf["scenes"] = json!([{"nodes": (0..nodes.len()).collect::<Vec<_>>()}]);
+        gltf["nodes"] = Value::Array(nodes);
+        gltf["meshes"] = Value::Array(meshes);
+        gltf["animations"] = Value::Array(animations);
+    }
+    if !builder.buffer.is_empty() {
+        gltf["buffers"] = json!([{"byteLength": builder.buffer.len()}]);
+        gltf["bufferViews"] = Value::Array(builder.views);
+        gltf["accessors"] = Value::Array(builder.accessors);
+    }
+
+    let mut json = serde_json::to_vec(&gltf)?;
+    pad(&mut json, b' ');
+    let mut bin = builder.buffer;
+    pad(&mut bin, 0);
+
+    let mut chunks = vec![(CHUNK_JSON, json)];
+    if !bin.is_empty() {
+        chunks.push((CHUNK_BIN, bin));
+    }
+    let length = 12 + chunks.iter().map(|(_, data)| 8 + data.len()).sum::<usize>();
+    let mut data = Vec::with_capacity(length);
+    data.extend_from_slice(GLB_MAGIC);
+    data.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    data.extend_from_slice(&(length as u32).to_le_bytes());
+    for (kind, chunk) in chunks {
+        data.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        data.extend_from_slice(&kind.to_le_bytes());
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+// GLB chunks are 4 byte aligned
+fn pad(data: &mut Vec<u8>, byte: u8) {
+    data.resize(data.len().next_multiple_of(4), byte);
+}
+
+/// Vertices of a disc for each point, the center followed by its rim
+struct Discs {
+    positions: Vec<Vec3>,
+    colors: Vec<Vec3>,
+}
+
+impl Discs {
+    fn new(points: &[Vec2], radius: f32, color: LinearRgba) -> Self {
+        let rim: Vec<Vec2> = (0..SEGMENTS)
+            .map(|i| Vec2::from_angle(i as f32 / SEGMENTS as f32 * TAU) * radius)
+            .collect();
+        let positions: Vec<Vec3> = points
+            .iter()
+            .flat_map(|point| {
+                std::iter::once(*point)
+                    .chain(rim.iter().map(move |offset| *point + *offset))
+                    .map(|p| (p / PIXELS_PER_METER).extend(0.0))
+            })
+            .collect();
+        let colors = vec![Vec3::new(color.red, color.green, color.blue); positions.len()];
+        Self { positions, colors }
+    }
+}
+
+// Counter clockwise triangle fans around each disc center
+fn disc_indices(discs: usize) -> Vec<u32> {
+    (0..discs)
+        .flat_map(|disc| {
+            let center = (disc * (SEGMENTS + 1)) as u32;
+            (0..SEGMENTS as u32).flat_map(move |i| {
+                [
+                    center,
+                    center + 1 + i,
+                    center + 1 + (i + 1) % SEGMENTS as u32,
+                ]
+            })
+        })
+        .collect()
+}
+
+/// Binary buffer with the views and accessors describing it
+#[derive(Default)]
+struct Builder {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Builder {
+    fn vec3(&mut self, values: &[Vec3], target: u32, bounds: bool) -> usize {
+        let mut accessor = json!({"componentType": FLOAT, "count": values.len(), "type": "VEC3"});
+        if bounds {
+            let min = values.iter().fold(Vec3::INFINITY, |min, v| min.min(*v));
+            let max = values.iter().fold(Vec3::NEG_INFINITY, |max, v| max.max(*v));
+            accessor["min"] = json!(min.to_array());
+            accessor["max"] = json!(max.to_array());
+        }
+        let bytes = values
+            .iter()
+            .flat_map(|v| v.to_array())
+            .map(f32::to_le_bytes);
+        self.push(bytes, Some(target), accessor)
+    }
+
+    fn scalars(&mut self, values: &[f32], bounds: bool) -> usize {
+        let mut accessor = json!({"componentType": FLOAT, "count": values.len(), "type": "SCALAR"});
+        if bounds {
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            accessor["min"] = json!([min]);
+            accessor["max"] = json!([max]);
+        }
+        self.push(values.iter().map(|v| v.to_le_bytes()), None, accessor)
+    }
+
+    fn indices(&mut self, values: &[u32]) -> usize {
+        let accessor =
+            json!({"componentType": UNSIGNED_INT, "count": values.len(), "type": "SCALAR"});
+        let bytes = values.iter().map(|v| v.to_le_bytes());
+        self.push(bytes, Some(ELEMENT_ARRAY_BUFFER), accessor)
+    }
+
+    // Append little endian values and return the index of their accessor
+    fn push(
+        &mut self,
+        bytes: impl Iterator<Item = [u8; 4]>,
+        target: Option<u32>,
+        mut accessor: Value,
+    ) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend(bytes.flatten());
+        let mut view =
+            json!({"buffer": 0, "byteOffset": offset, "byteLength": self.buffer.len() - offset});
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.views.push(view);
+        accessor["bufferView"] = json!(self.views.len() - 1);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        points::{Points, PointsSettings},
+        project::Drawing,
+    };
+
+    // Split a GLB into its JSON and binary chunks
+    fn parse(data: &[u8]) -> (Value, &[u8]) {
+        assert!(data.starts_with(GLB_MAGIC));
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        assert_eq!(u32_at(8) as usize, data.len());
+        let json_len = u32_at(12) as usize;
+        assert_eq!(u32_at(16), CHUNK_JSON);
+        let json = serde_json::from_slice(&data[20..20 + json_len]).unwrap();
+        let bin = &data[20 + json_len..];
+        if bin.is_empty() {
+            return (json, bin);
+        }
+        assert_eq!(u32_at(20 + json_len + 4), CHUNK_BIN);
+        (json, &bin[8..])
+    }
+
+    fn accessor_vec3(gltf: &Value, bin: &[u8], accessor: &Value, index: usize) -> Vec3 {
+        let accessor = &gltf["accessors"][accessor.as_u64().unwrap() as usize];
+        let view = &gltf["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize + index * 12;
+        let float = |i: usize| {
+            f32::from_le_bytes(bin[offset + i * 4..offset + i * 4 + 4].try_into().unwrap())
+        };
+        Vec3::new(float(0), float(1), float(2))
+    }
+
+    #[test]
+    fn test_glb() {
+        let project = Project {
+            drawings: vec![Drawing {
+                source_settings: PointsSettings {
+                    color: LinearRgba::RED,
+                    radius: 10.0,
+                },
+                target_settings: PointsSettings {
+                    color: LinearRgba::BLUE,
+                    radius: 20.0,
+                },
+                source_points: Points(vec![Vec2::ZERO, Vec2::new(100.0, 0.0)]),
+                target_points: Points(vec![Vec2::new(0.0, 100.0), Vec2::new(100.0, 100.0)]),
+                layer: 1.0,
+            }],
+            ..default()
+        };
+        let data = glb(&project).unwrap();
+        assert_eq!(data.len() % 4, 0);
+        let (gltf, bin) = parse(&data);
+        assert_eq!(
+            gltf["buffers"][0]["byteLength"].as_u64().unwrap() as usize,
+            bin.len()
+        );
+
+        let primitive = &gltf["meshes"][0]["primitives"][0];
+        let position = &primitive["attributes"]["POSITION"];
+        let target = &primitive["targets"][0]["POSITION"];
+        let count = (SEGMENTS + 1) * 2;
+        assert_eq!(
+            gltf["accessors"][position.as_u64().unwrap() as usize]["count"],
+            count
+        );
+        assert_eq!(
+            gltf["accessors"][primitive["indices"].as_u64().unwrap() as usize]["count"],
+            SEGMENTS * 3 * 2
+        );
+
+        // First rim vertex of the second disc, moved by the morph target
+        let index = SEGMENTS + 2;
+        let base = accessor_vec3(&gltf, bin, position, index);
+        let delta = accessor_vec3(&gltf, bin, target, index);
+        assert!(base.abs_diff_eq(Vec3::new(1.1, 0.0, 0.0), 1e-6));
+        assert!((base + delta).abs_diff_eq(Vec3::new(1.2, 1.0, 0.0), 1e-6));
+        let color = &primitive["targets"][0]["COLOR_0"];
+        assert_eq!(
+            accessor_vec3(&gltf, bin, color, 0),
+            Vec3::new(-1.0, 0.0, 1.0)
+        );
+
+        let animation = &gltf["animations"][0];
+        assert_eq!(animation["channels"][0]["target"]["path"], "weights");
+        let input =
+            &gltf["accessors"][animation["samplers"][0]["input"].as_u64().unwrap() as usize];
+        assert_eq!(input["max"][0], project.settings.duration * 2.0);
+    }
+
+    #[test]
+    fn test_empty_glb() {
+        let data = glb(&Project::default()).unwrap();
+        let (gltf, bin) = parse(&data);
+        assert!(bin.is_empty());
+        for key in ["buffers", "nodes", "meshes", "animations"] {
+            assert!(gltf.get(key).is_none(), "{} should be left out", key);
+        }
+        assert_eq!(gltf["scenes"], json!([{}]));
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use bevy::prelude::*;
+use serde_json::{Value, json};
+
+use super::{KEYFRAMES_PER_SECOND, Keyframes, view};
+use crate::{
+    points::{Points, PointsSettings},
+    project::Project,
+};
+
+/// Bodymovin version of the exported format
+const VERSION: &str = "5.7.4";
+/// Lottie stroke line cap and join
+const ROUND: u32 = 2;
+
+/// Export the project as Lottie (Bodymovin) JSON, animating from source to target and back
+/// with the same timing and easing as the player.
+/// Each drawing is a shape layer, with a zero length round capped path for each point
+/// (as in the SVG export) so it renders as the same disc `points.wgsl` draws.
+pub(crate) fn animation(project: &Project) -> Result<Vec<u8>> {
+    let keyframes = Keyframes::new(project.settings.duration, project.settings.easing)?;
+    let view = view(project);
+    // Lottie is y down from the top left
+    let to_lottie = |point: Vec2| [round(point.x - view.min.x), round(view.max.y - point.y)];
+    let shape = |points: &Points, index: usize| {
+        let vertex = to_lottie(points.0[index]);
+        json!({
+            "c": false,
+            "v": [vertex, vertex],
+            "i": [[0, 0], [0, 0]],
+            "o": [[0, 0], [0, 0]],
+        })
+    };
+    let frames = (keyframes.duration * KEYFRAMES_PER_SECOND).round() as u32;
+
+    // Lottie draws the first layer on top
+    let mut layers = Vec::new();
+    for (index, drawing) in project.layered().into_iter().enumerate().rev() {
+        if drawing.source_points.0.is_empty() {
+            continue;
+        }
+        let (source, target) = (&drawing.source_points, &drawing.target_points);
+        let count = source.0.len().min(target.0.len());
+        // Mix each keyframe once, every point's path indexes into it
+        let mixed: Vec<Points> = keyframes
+            .frames
+            .iter()
+            .map(|(_, t)| source.mix(target, *t))
+            .collect();
+        let mut items: Vec<Value> = (0..count)
+            .map(|point| {
+                json!({
+                    "ty": "sh",
+                    "nm": format!("point {}", point),
+                    "ks": property(&keyframes, |key| shape(&mixed[key], point)),
+                })
+            })
+            .collect();
+        let settings: Vec<PointsSettings> = keyframes
+            .frames
+            .iter()
+            .map(|(_, t)| drawing.source_settings.mix(&drawing.target_settings, *t))
+            .collect();
+        items.push(json!({
+            "ty": "st",
+            "nm": "stroke",
+            "c": property(&keyframes, |key| color(&settings[key])),
+            "o": {"a": 0, "k": 100},
+            "w": property(&keyframes, |key| json!(round(settings[key].radius * 2.0))),
+            "lc": ROUND,
+            "lj": ROUND,
+        }));
+        items.push(json!({
+            "ty": "tr",
+            "p": {"a": 0, "k": [0, 0]},
+            "a": {"a": 0, "k": [0, 0]},
+            "s": {"a": 0, "k": [100, 100]},
+            "r": {"a": 0, "k": 0},
+            "o": {"a": 0, "k": 100},
+        }));
+
+        layers.push(json!({
+            "ddd": 0,
+            "ind": index + 1,
+            "ty": 4,
+            "nm": format!("drawing {}", index),
+            "sr": 1,
+            "ks": layer_transform(),
+            "ao": 0,
+            "shapes": [{"ty": "gr", "nm": "points", "it": items}],
+            "ip": 0,
+            "op": frames,
+            "st": 0,
+            "bm": 0,
+        }));
+    }
+
+    // Solid background at the bottom, the size of the canvas
+    let [r, g, b, _] = Srgba::from(project.settings.background).to_u8_array();
+    layers.push(json!({
+        "ddd": 0,
+        "ind": project.drawings.len() + 1,
+        "ty": 1,
+        "nm": "background",
+        "sr": 1,
+        "ks": layer_transform(),
+        "ao": 0,
+        "sc": format!("#{:02x}{:02x}{:02x}", r, g, b),
+        "sw": view.width().ceil() as u32,
+        "sh": view.height().ceil() as u32,
+        "ip": 0,
+        "op": frames,
+        "st": 0,
+        "bm": 0,
+    }));
+
+    let lottie = json!({
+        "v": VERSION,
+        "nm": clap::crate_name!(),
+        // Some players only accept whole numbers here
+        "fr": KEYFRAMES_PER_SECOND as u32,
+        "ip": 0,
+        "op": frames,
+        "w": view.width().ceil() as u32,
+        "h": view.height().ceil() as u32,
+        "ddd": 0,
+        "assets": [],
+        "layers": layers,
+    });
+    let mut data = serde_json::to_vec(&lottie)?;
+    data.push(b'\n');
+    Ok(data)
+}
+
+// Layers are not transformed, shapes are positioned on the canvas
+fn layer_transform() -> Value {
+    json!({
+        "o": {"a": 0, "k": 100},
+        "r": {"a": 0, "k": 0},
+        "p": {"a": 0, "k": [0, 0, 0]},
+        "a": {"a": 0, "k": [0, 0, 0]},
+        "s": {"a": 0, "k": [100, 100, 100]},
+    })
+}
+
+// Animated property, or a static one if the value never changes.
+// `value` is called with the index of each keyframe.
+fn property(keyframes: &Keyframes, value: impl Fn(usize) -> Value) -> Value {
+    let values: Vec<Value> = (0..keyframes.frames.len()).map(value).collect();
+    if values.iter().all(|v| *v == values[0]) {
+        return json!({"a": 0, "k": values[0]});
+    }
+    let last = values.len() - 1;
+    let frames = keyframes
+        .frames
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(index, ((time, _), value))| {
+            let mut frame = json!({
+                "t": round(time * keyframes.duration * KEYFRAMES_PER_SECOND),
+                // Keyframe values are always arrays, shapes are wrapped too
+                "s": match value {
+                    Value::Array(_) => value,
+                    value => json!([value]),
+                },
+            });
+            if index < last {
+                // Ease out of this keyframe and into the next
+                let [out, into] = match keyframes.splines {
+                    Some(ref splines) => splines[index],
+                    None => [Vec2::ZERO, Vec2::ONE],
+                };
+                frame["o"] = json!({"x": [round(out.x)], "y": [round(out.y)]});
+                frame["i"] = json!({"x": [round(into.x)], "y": [round(into.y)]});
+            }
+            frame
+        })
+        .collect::<Vec<_>>();
+    json!({"a": 1, "k": frames})
+}
+
+/// Materials are opaque, so alpha is ignored
+fn color(settings: &PointsSettings) -> Value {
+    let [r, g, b, _] = Srgba::from(settings.color).to_f32_array();
+    json!([round(r), round(g), round(b), 1])
+}
+
+// Keep the JSON compact, to a hundredth of a pixel
+fn round(value: f32) -> f64 {
+    (value as f64 * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::Drawing;
+
+    #[test]
+    fn test_animation() {
+        let project = Project {
+            drawings: vec![
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::RED,
+                        radius: 2.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::BLUE,
+                        radius: 4.0,
+                    },
+                    source_points: Points(vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 10.0)]),
+                    target_points: Points(vec![Vec2::new(10.0, 0.0), Vec2::new(20.0, -10.0)]),
+                    layer: 2.0,
+                },
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::GREEN,
+                        radius: 1.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::GREEN,
+                        radius: 1.0,
+                    },
+                    source_points: Points(vec![Vec2::new(5.0, 5.0)]),
+                    target_points: Points(vec![Vec2::new(5.0, 5.0)]),
+                    layer: 1.0,
+                },
+            ],
+            ..default()
+        };
+        let lottie: Value = serde_json::from_slice(&animation(&project).unwrap()).unwrap();
+        assert_eq!(lottie["fr"], 30);
+        assert_eq!(lottie["op"], 150);
+        // The canvas is centered on the origin
+        assert_eq!(lottie["w"], 600);
+        assert_eq!(lottie["h"], 600);
+
+        // The top layer comes first
+        let layers = lottie["layers"].as_array().unwrap();
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[2]["sc"], "#2b2c2f");
+        let items = &layers[0]["shapes"][0]["it"];
+        let path = &items[1]["ks"];
+        assert_eq!(path["a"], 1);
+        let frames = path["k"].as_array().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1]["t"], 75.0);
+        assert_eq!(
+            frames[0]["s"][0]["v"],
+            json!([[310.0, 290.0], [310.0, 290.0]])
+        );
+        assert_eq!(
+            frames[1]["s"][0]["v"],
+            json!([[320.0, 310.0], [320.0, 310.0]])
+        );
+        assert_eq!(frames[0]["o"], json!({"x": [0.65], "y": [0.0]}));
+        assert_eq!(frames[0]["i"], json!({"x": [0.35], "y": [1.0]}));
+        assert!(frames[2].get("o").is_none());
+
+        let stroke = &items[2];
+        assert_eq!(stroke["w"]["k"][1]["s"], json!([8.0]));
+        assert_eq!(stroke["c"]["k"][0]["s"], json!([1.0, 0.0, 0.0, 1]));
+
+        // Nothing changes in the bottom layer
+        let items = &layers[1]["shapes"][0]["it"];
+        assert_eq!(items[0]["ks"]["a"], 0);
+        assert_eq!(items[1]["w"], json!({"a": 0, "k": 2.0}));
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use image::RgbaImage;
+
+use crate::{project::Project, render::Renderer};
+
+/// Render each frame to a numbered PNG file (`frame-00000.png`, ...) in `directory`, creating it if needed
+pub(crate) fn png(project: &Project, size: UVec2, frames: &[f32], directory: &Path) -> Result<()> {
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+    let renderer = Renderer::new(size);
+    for (index, t) in frames.iter().enumerate() {
+        let path = directory.join(format!("frame-{:05}.png", index));
+        renderer
+            .render(project, *t)
+            .save(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Stream frames as uncompressed YUV4MPEG2 (4:4:4, BT.601 limited range) video,
+/// which most video encoders accept directly (e.g. `ffmpeg -i - out.mp4`)
+pub(crate) fn y4m(
+    project: &Project,
+    size: UVec2,
+    fps: u32,
+    frames: &[f32],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444 XCOLORRANGE=LIMITED",
+        size.x, size.y, fps
+    )?;
+    let renderer = Renderer::new(size);
+    let mut planes = Vec::with_capacity(size.element_product() as usize * 3);
+    for t in frames {
+        writer.write_all(b"FRAME\n")?;
+        yuv_planes(&renderer.render(project, *t), &mut planes);
+        writer.write_all(&planes)?;
+    }
+    writer.flush()
+}
+
+// Convert to planar Y, Cb, Cr
+fn yuv_planes(image: &RgbaImage, planes: &mut Vec<u8>) {
+    const Y: [f32; 3] = [65.481, 128.553, 24.966];
+    const CB: [f32; 3] = [-37.797, -74.203, 112.0];
+    const CR: [f32; 3] = [112.0, -93.786, -18.214];
+    planes.clear();
+    for (coefficients, offset) in [(Y, 16.0), (CB, 128.0), (CR, 128.0)] {
+        let coefficients = Vec3::from_array(coefficients) / 255.0;
+        planes.extend(image.pixels().map(|pixel| {
+            let [r, g, b, _] = pixel.0;
+            let rgb = Vec3::new(r as f32, g as f32, b as f32);
+            (coefficients.dot(rgb) + offset).round() as u8
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_y4m() {
+        let project = Project::from_bytes(include_bytes!("../../web/demo/x.terp")).unwrap();
+        let mut data = Vec::new();
+        y4m(&project, UVec2::new(4, 2), 30, &[0.0, 1.0], &mut data).unwrap();
+        let header = b"YUV4MPEG2 W4 H2 F30:1 Ip A1:1 C444 XCOLORRANGE=LIMITED\n";
+        assert!(data.starts_with(header));
+        let frame = b"FRAME\n".len() + 4 * 2 * 3;
+        assert_eq!(data.len(), header.len() + frame * 2);
+        assert!(data[header.len()..].starts_with(b"FRAME\n"));
+        assert!(data[header.len() + frame..].starts_with(b"FRAME\n"));
+    }
+
+    #[test]
+    fn test_yuv_planes() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 255]));
+        let mut planes = Vec::new();
+        yuv_planes(&image, &mut planes);
+        assert_eq!(planes, vec![16, 235, 128, 128, 128, 128]);
+
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        yuv_planes(&image, &mut planes);
+        assert_eq!(planes, vec![81, 235, 90, 128, 240, 128]);
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{Result, bail};
+use bevy::prelude::*;
+use image::{RgbaImage, imageops};
+use serde::Serialize;
+
+use crate::{project::Project, render::Renderer};
+
+/// Largest sheet width or height, many engines and GPUs can't load bigger textures
+pub(crate) const MAX_SIZE: u32 = 4096;
+
+/// Frames packed into a grid on one image, with an atlas describing where each frame is
+pub(crate) struct SpriteSheet {
+    pub(crate) image: RgbaImage,
+    pub(crate) atlas: Atlas,
+}
+
+/// Sprite sheet description, in the Aseprite/TexturePacker JSON array layout
+/// that most game engines and sprite tools can import
+#[derive(Serialize)]
+pub(crate) struct Atlas {
+    frames: Vec<AtlasFrame>,
+    meta: AtlasMeta,
+}
+
+#[derive(Serialize)]
+struct AtlasFrame {
+    filename: String,
+    frame: AtlasRect,
+    rotated: bool,
+    trimmed: bool,
+    #[serde(rename = "spriteSourceSize")]
+    sprite_source_size: AtlasRect,
+    #[serde(rename = "sourceSize")]
+    source_size: AtlasSize,
+    /// Milliseconds to display the frame
+    duration: u32,
+}
+
+#[derive(Serialize)]
+struct AtlasMeta {
+    app: &'static str,
+    version: &'static str,
+    image: String,
+    format: &'static str,
+    size: AtlasSize,
+    scale: &'static str,
+}
+
+#[derive(Serialize)]
+struct AtlasRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct AtlasSize {
+    w: u32,
+    h: u32,
+}
+
+/// Render `frames` at `fps` into a grid `columns` wide (square-ish if `None`).
+/// `image` is the file name of the sheet image, referenced from the atlas.
+/// Fails if the sheet would be larger than `MAX_SIZE`.
+pub(crate) fn render(
+    project: &Project,
+    size: UVec2,
+    fps: u32,
+    frames: &[f32],
+    columns: Option<u32>,
+    image: &str,
+) -> Result<SpriteSheet> {
+    let count = frames.len() as u32;
+    let columns = columns
+        .unwrap_or_else(|| (count as f32).sqrt().ceil() as u32)
+        .clamp(1, count.max(1));
+    let rows = count.div_ceil(columns);
+    let (width, height) = (columns as u64 * size.x as u64, rows as u64 * size.y as u64);
+    if width.max(height) > MAX_SIZE as u64 {
+        bail!(
+            "The sprite sheet would be {}x{} pixels, more than the {} pixel limit of many engines. \
+             Use a lower --fps, --width or --height, or set --columns",
+            width,
+            height,
+            MAX_SIZE
+        );
+    }
+    let mut sheet = RgbaImage::new(columns * size.x, rows * size.y);
+    let renderer = Renderer::new(size);
+    let duration = (1000.0 / fps as f32).round() as u32;
+
+    let frames = frames
+        .iter()
+        .enumerate()
+        .map(|(index, t)| {
+            let index = index as u32;
+            let position = UVec2::new(index % columns, index / columns) * size;
+            imageops::replace(
+                &mut sheet,
+                &renderer.render(project, *t),
+                position.x as i64,
+                position.y as i64,
+            );
+            AtlasFrame {
+                filename: format!("frame-{:05}", index),
+                frame: AtlasRect {
+                    x: position.x,
+                    y: position.y,
+                    w: size.x,
+                    h: size.y,
+                },
+                rotated: false,
+                trimmed: false,
+                sprite_source_size: AtlasRect {
+                    x: 0,
+                    y: 0,
+                    w: size.x,
+                    h: size.y,
+                },
+                source_size: AtlasSize {
+                    w: size.x,
+                    h: size.y,
+                },
+                duration,
+            }
+        })
+        .collect();
+
+    Ok(SpriteSheet {
+        atlas: Atlas {
+            frames,
+            meta: AtlasMeta {
+                app: clap::crate_name!(),
+                version: clap::crate_version!(),
+                image: image.into(),
+                format: "RGBA8888",
+                size: AtlasSize {
+                    w: sheet.width(),
+                    h: sheet.height(),
+                },
+                scale: "1",
+            },
+        },
+        image: sheet,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let project = Project::from_bytes(include_bytes!("../../web/demo/x.terp")).unwrap();
+        let frames = [0.0, 0.25, 0.5, 0.75, 1.0];
+        let sheet = render(&project, UVec2::new(20, 10), 25, &frames, None, "x.png").unwrap();
+        // 5 frames fit in 3 columns and 2 rows
+        assert_eq!(sheet.image.dimensions(), (60, 20));
+
+        let atlas = serde_json::to_value(&sheet.atlas).unwrap();
+        assert_eq!(atlas["frames"].as_array().unwrap().len(), 5);
+        assert_eq!(
+            atlas["frames"][4]["frame"],
+            serde_json::json!({"x": 20, "y": 10, "w": 20, "h": 10})
+        );
+        assert_eq!(atlas["frames"][0]["duration"], 40);
+        assert_eq!(atlas["meta"]["image"], "x.png");
+        assert_eq!(atlas["meta"]["size"], serde_json::json!({"w": 60, "h": 20}));
+
+        let sheet = render(&project, UVec2::new(20, 10), 25, &frames, Some(5), "x.png").unwrap();
+        assert_eq!(sheet.image.dimensions(), (100, 10));
+
+        // A 5 second cycle of 600x600 frames at 25fps is too big for one sheet
+        let frames = vec![0.0; 125];
+        let err = render(&project, UVec2::splat(600), 25, &frames, None, "x.png")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("7200x6600"));
+    }
+}
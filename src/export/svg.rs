@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use bevy::prelude::*;
+
+use super::{Keyframes, number, view};
+use crate::{points::Points, project::Project};
+
+/// Export the project at interpolation `t` (0 is source, 1 is target) as an SVG document.
+/// Each point is a zero length round capped stroke, so it renders as the same disc `points.wgsl` draws.
+pub(crate) fn frame(project: &Project, t: f32) -> Result<String> {
+    let mut svg = String::new();
+    write_header(&mut svg, project)?;
+    for drawing in project.layered() {
+        let (points, settings) = drawing.interpolate(t);
+        if points.0.is_empty() {
+            continue;
+        }
+        writeln!(
+            svg,
+            r#"  <path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+            path_data(&points),
+            color(settings.color),
+            number(settings.radius * 2.0),
+        )?;
+    }
+    writeln!(svg, "</svg>")?;
+    Ok(svg)
+}
+
+/// Export the project as a self animating SVG document.
+/// SMIL animations morph each drawing from source to target and back again,
+/// with the same timing and easing as the player. Viewers without SMIL show the source.
+pub(crate) fn animated(project: &Project) -> Result<String> {
+    let keyframes = Keyframes::new(project.settings.duration, project.settings.easing)?;
+    let mut svg = String::new();
+    write_header(&mut svg, project)?;
+    for drawing in project.layered() {
+        if drawing.source_points.0.is_empty() {
+            continue;
+        }
+        let (source, target) = (&drawing.source_settings, &drawing.target_settings);
+        writeln!(
+            svg,
+            r#"  <path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round">"#,
+            path_data(&drawing.source_points),
+            color(source.color),
+            number(source.radius * 2.0),
+        )?;
+        write_animate(&mut svg, &keyframes, "d", |t| {
+            path_data(&drawing.source_points.mix(&drawing.target_points, t))
+        })?;
+        write_animate(&mut svg, &keyframes, "stroke", |t| {
+            color(source.mix(target, t).color)
+        })?;
+        write_animate(&mut svg, &keyframes, "stroke-width", |t| {
+            number(source.mix(target, t).radius * 2.0)
+        })?;
+        writeln!(svg, "  </path>")?;
+    }
+    writeln!(svg, "</svg>")?;
+    Ok(svg)
+}
+
+fn write_animate(
+    svg: &mut String,
+    keyframes: &Keyframes,
+    attribute: &str,
+    value: impl Fn(f32) -> String,
+) -> Result<()> {
+    let values: Vec<String> = keyframes.frames.iter().map(|(_, t)| value(*t)).collect();
+    // Nothing to animate if the attribute never changes
+    if values.iter().all(|v| *v == values[0]) {
+        return Ok(());
+    }
+    let key_times: Vec<String> = keyframes
+        .frames
+        .iter()
+        .map(|(time, _)| time.to_string())
+        .collect();
+    write!(
+        svg,
+        r#"    <animate attributeName="{}" dur="{}s" repeatCount="indefinite" keyTimes="{}" values="{}""#,
+        attribute,
+        keyframes.duration,
+        key_times.join(";"),
+        values.join(";"),
+    )?;
+    if let Some(ref splines) = keyframes.splines {
+        let splines: Vec<String> = splines
+            .iter()
+            .map(|[p1, p2]| format!("{} {} {} {}", p1.x, p1.y, p2.x, p2.y))
+            .collect();
+        write!(
+            svg,
+            r#" calcMode="spline" keySplines="{}""#,
+            splines.join(";")
+        )?;
+    }
+    writeln!(svg, "/>")?;
+    Ok(())
+}
+
+pub(super) fn write_header(svg: &mut String, project: &Project) -> Result<()> {
+    let bounds = view(project);
+    let size = bounds.size();
+    writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="{x} {y} {w} {h}">"#,
+        x = number(bounds.min.x),
+        // World space is y up, SVG is y down
+        y = number(-bounds.max.y),
+        w = number(size.x),
+        h = number(size.y),
+    )?;
+    writeln!(
+        svg,
+        r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        number(bounds.min.x),
+        number(-bounds.max.y),
+        number(size.x),
+        number(size.y),
+        color(project.settings.background),
+    )?;
+    Ok(())
+}
+
+/// Path with a zero length segment at each point
+pub(super) fn path_data(points: &Points) -> String {
+    let mut data = String::with_capacity(points.0.len() * 16);
+    for point in &points.0 {
+        let _ = write!(data, "M{} {}h0", number(point.x), number(-point.y));
+    }
+    data
+}
+
+/// Materials are opaque, so alpha is ignored
+pub(super) fn color(color: LinearRgba) -> String {
+    Srgba::from(color).with_alpha(1.0).to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{points::PointsSettings, project::Drawing};
+
+    fn project() -> Project {
+        Project {
+            drawings: vec![
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::RED,
+                        radius: 2.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::BLUE,
+                        radius: 4.0,
+                    },
+                    source_points: Points(vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 10.0)]),
+                    target_points: Points(vec![Vec2::new(10.0, 0.0), Vec2::new(20.0, -10.0)]),
+                    layer: 2.0,
+                },
+                Drawing {
+                    source_settings: PointsSettings {
+                        color: LinearRgba::GREEN,
+                        radius: 1.0,
+                    },
+                    target_settings: PointsSettings {
+                        color: LinearRgba::GREEN,
+                        radius: 1.0,
+                    },
+                    source_points: Points(vec![Vec2::new(5.0, 5.0)]),
+                    target_points: Points(vec![Vec2::new(5.0, 5.0)]),
+                    layer: 1.0,
+                },
+            ],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut cropped = project();
+        cropped.settings.canvas = Vec2::new(26.0, 24.0);
+        let svg = frame(&cropped, 0.0).unwrap();
+        assert!(svg.contains(r#"viewBox="-13 -12 26 24""#));
+        assert!(svg.contains(r##"<rect x="-13" y="-12" width="26" height="24" fill="#2B2C2F"/>"##));
+        let paths: Vec<&str> = svg.lines().filter(|l| l.contains("<path")).collect();
+        assert_eq!(paths.len(), 2);
+        // Lower layer first
+        assert!(paths[0].contains(r##"stroke="#00FF00""##));
+        assert!(paths[1].contains(r#"d="M0 0h0M10 -10h0""#));
+        assert!(paths[1].contains(r##"stroke="#FF0000""##));
+        assert!(paths[1].contains(r#"stroke-width="4""#));
+
+        let svg = frame(&project(), 0.5).unwrap();
+        assert!(svg.contains(r#"d="M5 0h0M15 0h0""#));
+        assert!(svg.contains(r#"stroke-width="6""#));
+
+        let svg = frame(&project(), 1.0).unwrap();
+        assert!(svg.contains(r##"stroke="#0000FF""##));
+    }
+
+    #[test]
+    fn test_animated() {
+        let svg = animated(&project()).unwrap();
+        let animations: Vec<&str> = svg.lines().filter(|l| l.contains("<animate")).collect();
+        // The second drawing does not change
+        assert_eq!(animations.len(), 3);
+        assert!(animations[0].contains(r#"attributeName="d""#));
+        assert!(animations[0].contains(r#"dur="5s""#));
+        assert!(animations[0].contains(r#"repeatCount="indefinite""#));
+        assert!(animations[0].contains(r#"keyTimes="0;0.5;1""#));
+        assert!(
+            animations[0].contains(r#"values="M0 0h0M10 -10h0;M10 0h0M20 10h0;M0 0h0M10 -10h0""#)
+        );
+        assert!(animations[0].contains(r#"calcMode="spline""#));
+        assert!(animations[1].contains(r##"values="#FF0000;#0000FF;#FF0000""##));
+        assert!(animations[2].contains(r#"values="4;8;4""#));
+        assert_eq!(svg.matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let svg = frame(&Project::default(), 0.0).unwrap();
+        assert!(svg.contains(r#"viewBox="-300 -300 600 600""#));
+        assert!(!svg.contains("<path"));
+    }
+}
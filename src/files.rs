@@ -0,0 +1,305 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+};
+use tinyfiledialogs::{MessageBoxIcon, YesNo};
+
+use crate::{
+    autosave::{Recovery, unsaved_changes},
+    cli,
+    config::Config,
+    error_handler,
+    keymap::{RegisterCommand, command_just_pressed},
+    project::{CurrentProject, LoadProject, Project},
+};
+
+const FILTER_PATTERNS: &[&str] = &["*.terp", "*.ron", "*.terp.json"];
+const FILTER_DESCRIPTION: &str = "Terp projects";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Dialog>()
+        .init_resource::<Config>()
+        .register_command("new", "Start a new project", &["N", "Ctrl+N"])
+        .register_command("open", "Open a project", &["O", "Ctrl+O"])
+        .register_command(
+            "save-as",
+            "Save the project under a new name",
+            &["Shift+S", "Ctrl+Shift+S"],
+        )
+        .add_event::<OpenProject>()
+        .add_systems(
+            Update,
+            (
+                new_project.run_if(command_just_pressed("new").and(not(unsaved_changes))),
+                confirm_new_project.run_if(command_just_pressed("new").and(unsaved_changes)),
+                open_project.run_if(command_just_pressed("open").and(not(unsaved_changes))),
+                confirm_open_project.run_if(command_just_pressed("open").and(unsaved_changes)),
+                open_requested_project,
+                save_project_as.run_if(
+                    command_just_pressed("save-as")
+                        .or(command_just_pressed("save").and(never_saved)),
+                ),
+                chosen_file.pipe(error_handler),
+            ),
+        );
+}
+
+/// Project file to open in place of the current one, once any unsaved changes are discarded
+#[derive(Event, Debug)]
+pub(crate) struct OpenProject(pub(crate) PathBuf);
+
+/// File dialog running in the background so the editor keeps drawing, only one is shown at a time
+#[derive(Resource, Default)]
+struct Dialog(Option<Task<Option<Chosen>>>);
+
+enum Chosen {
+    New,
+    Open(PathBuf),
+    SaveAs(PathBuf),
+}
+
+// Saving a project that has never been saved asks where to save it
+fn never_saved(args: Res<cli::Args>) -> bool {
+    args.project().is_none()
+}
+
+fn new_project(mut args: ResMut<cli::Args>, config: Res<Config>, mut commands: Commands) {
+    start_new_project(&mut args, &config, &mut commands);
+}
+
+fn start_new_project(args: &mut cli::Args, config: &Config, commands: &mut Commands) {
+    args.set_project(None);
+    commands.send_event(LoadProject(config.new_project()));
+}
+
+// Unsaved changes can't be undone once the project is replaced
+fn confirm_new_project(mut dialog: ResMut<Dialog>) {
+    if dialog.0.is_some() {
+        return;
+    }
+    dialog.0 = Some(
+        IoTaskPool::get()
+            .spawn(async move { discard_changes("New Project").then_some(Chosen::New) }),
+    );
+}
+
+// Blocks until the user answers, so only call it from a dialog task
+fn discard_changes(title: &str) -> bool {
+    tinyfiledialogs::message_box_yes_no(
+        title,
+        "Discard unsaved changes to the current project?",
+        MessageBoxIcon::Question,
+        YesNo::No,
+    ) == YesNo::Yes
+}
+
+fn open_project(dialog: ResMut<Dialog>, args: Res<cli::Args>) {
+    choose_project(dialog, &args, false);
+}
+
+fn confirm_open_project(dialog: ResMut<Dialog>, args: Res<cli::Args>) {
+    choose_project(dialog, &args, true);
+}
+
+fn choose_project(mut dialog: ResMut<Dialog>, args: &cli::Args, confirm: bool) {
+    if dialog.0.is_some() {
+        return;
+    }
+    let path = default_path(args);
+    dialog.0 = Some(IoTaskPool::get().spawn(async move {
+        if confirm && !discard_changes("Open Project") {
+            return None;
+        }
+        tinyfiledialogs::open_file_dialog(
+            "Open Project",
+            &path,
+            Some((FILTER_PATTERNS, FILTER_DESCRIPTION)),
+        )
+        .map(|path| Chosen::Open(path.into()))
+    }));
+}
+
+// Projects opened other ways (e.g. dropped on the window) are confirmed like the open dialog
+fn open_requested_project(
+    mut events: EventReader<OpenProject>,
+    mut dialog: ResMut<Dialog>,
+    mut args: ResMut<cli::Args>,
+    recovery: Res<Recovery>,
+    current: CurrentProject,
+    mut commands: Commands,
+) {
+    // Projects replace each other, so only the last of several opened at once matters
+    let Some(OpenProject(path)) = events.read().last() else {
+        return;
+    };
+    if !unsaved_changes(recovery, current) {
+        // Report a project that fails to load, the current one is kept
+        error_handler(In(open(path, &mut args, &mut commands)));
+        return;
+    }
+    if dialog.0.is_some() {
+        warn!("Not opening {}, another dialog is open", path.display());
+        return;
+    }
+    let path = path.clone();
+    dialog.0 = Some(
+        IoTaskPool::get()
+            .spawn(async move { discard_changes("Open Project").then_some(Chosen::Open(path)) }),
+    );
+}
+
+// Only a file that loads becomes the project, so saving never overwrites anything else
+fn open(path: &Path, args: &mut cli::Args, commands: &mut Commands) -> Result<()> {
+    commands.send_event(LoadProject(Project::load(path)?));
+    args.set_project(Some(path));
+    Ok(())
+}
+
+fn save_project_as(mut dialog: ResMut<Dialog>, args: Res<cli::Args>) {
+    if dialog.0.is_some() {
+        return;
+    }
+    let path = default_path(&args);
+    dialog.0 = Some(IoTaskPool::get().spawn(async move {
+        tinyfiledialogs::save_file_dialog_with_filter(
+            "Save Project As",
+            &path,
+            FILTER_PATTERNS,
+            FILTER_DESCRIPTION,
+        )
+        .map(|path| Chosen::SaveAs(path.into()))
+    }));
+}
+
+// Start the dialog at the current project
+fn default_path(args: &cli::Args) -> String {
+    args.project().map_or_else(
+        || "untitled.terp".into(),
+        |project| project.to_string_lossy().into_owned(),
+    )
+}
+
+fn chosen_file(
+    mut dialog: ResMut<Dialog>,
+    mut args: ResMut<cli::Args>,
+    current: CurrentProject,
+    config: Res<Config>,
+    mut commands: Commands,
+) -> Result<()> {
+    let Some(task) = dialog.0.as_mut() else {
+        return Ok(());
+    };
+    let Some(chosen) = block_on(future::poll_once(task)) else {
+        return Ok(());
+    };
+    dialog.0 = None;
+    match chosen {
+        Some(Chosen::New) => start_new_project(&mut args, &config, &mut commands),
+        Some(Chosen::Open(path)) => open(&path, &mut args, &mut commands)?,
+        Some(Chosen::SaveAs(path)) => {
+            commands.send_event(current.save(&path)?);
+            args.set_project(Some(&path));
+        }
+        // Cancelled
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        points::PointsMaterial,
+        project::{ProjectSettings, SaveProjectData},
+    };
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(cli::Args::new(Some("old.terp")))
+            .init_resource::<Dialog>()
+            .init_resource::<Config>()
+            .init_resource::<ProjectSettings>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<PointsMaterial>>()
+            .add_event::<LoadProject>()
+            .add_event::<SaveProjectData>()
+            .add_systems(Update, chosen_file.pipe(error_handler));
+        app
+    }
+
+    fn choose(app: &mut App, chosen: Option<Chosen>) {
+        app.world_mut().resource_mut::<Dialog>().0 =
+            Some(IoTaskPool::get().spawn(async move { chosen }));
+        for _ in 0..1000 {
+            app.update();
+            if app.world().resource::<Dialog>().0.is_none() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("dialog never finished");
+    }
+
+    #[test]
+    fn test_save_as() {
+        let mut app = setup();
+        choose(&mut app, Some(Chosen::SaveAs("new.terp.ron".into())));
+
+        assert_eq!(
+            app.world().resource::<cli::Args>().project(),
+            Some(Path::new("new.terp.ron"))
+        );
+        let events = app.world().resource::<Events<SaveProjectData>>();
+        let mut cursor = events.get_cursor();
+        let event = cursor.read(events).last().unwrap();
+        assert_eq!(event.path, Path::new("new.terp.ron"));
+        // Saved in the format of the new name
+        assert!(event.data.starts_with(b"("));
+    }
+
+    #[test]
+    fn test_confirm_new() {
+        let mut app = setup();
+        choose(&mut app, Some(Chosen::New));
+
+        assert_eq!(app.world().resource::<cli::Args>().project(), None);
+        assert!(!app.world().resource::<Events<LoadProject>>().is_empty());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut app = setup();
+        choose(&mut app, None);
+
+        assert_eq!(
+            app.world().resource::<cli::Args>().project(),
+            Some(Path::new("old.terp"))
+        );
+        assert!(app.world().resource::<Events<SaveProjectData>>().is_empty());
+        assert!(app.world().resource::<Events<LoadProject>>().is_empty());
+    }
+
+    #[test]
+    fn test_new_project() {
+        let mut config = Config::default();
+        config.animation.duration = 4.0;
+        let mut app = App::new();
+        app.insert_resource(cli::Args::new(Some("old.terp")))
+            .insert_resource(config)
+            .add_event::<LoadProject>()
+            .add_systems(Update, new_project);
+        app.update();
+
+        assert_eq!(app.world().resource::<cli::Args>().project(), None);
+        let events = app.world().resource::<Events<LoadProject>>();
+        let mut cursor = events.get_cursor();
+        let LoadProject(project) = cursor.read(events).last().unwrap();
+        // New projects use the configured settings
+        assert_eq!(project.settings.duration, 4.0);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    points::{Points, PointsSettings},
+    project::Drawing,
+};
+
+pub(crate) mod svg;
+
+/// A single continuous stroke read from another format
+pub(crate) struct Stroke {
+    pub(crate) points: Points,
+    pub(crate) settings: PointsSettings,
+}
+
+/// Pair source and target strokes in order into drawings, layered from `layer` up.
+/// A stroke without a counterpart on the other side is paired with itself.
+pub(crate) fn pair(source: Vec<Stroke>, target: Vec<Stroke>, layer: f32) -> Vec<Drawing> {
+    let count = source.len().max(target.len());
+    let mut source = source.into_iter();
+    let mut target = target.into_iter();
+    (0..count)
+        .filter_map(|i| {
+            let (source, target) = match (source.next(), target.next()) {
+                (Some(source), Some(target)) => (source, target),
+                (Some(stroke), None) | (None, Some(stroke)) => (
+                    Stroke {
+                        points: stroke.points.clone(),
+                        settings: stroke.settings,
+                    },
+                    stroke,
+                ),
+                (None, None) => return None,
+            };
+            // Source and target must have the same number of points to interpolate
+            let len = source.points.0.len().max(target.points.0.len());
+            Some(Drawing {
+                source_settings: source.settings,
+                target_settings: target.settings,
+                source_points: source.points.resample(len),
+                target_points: target.points.resample(len),
+                layer: layer + i as f32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+
+    fn stroke(points: Vec<Vec2>, radius: f32) -> Stroke {
+        Stroke {
+            points: Points(points),
+            settings: PointsSettings {
+                color: LinearRgba::WHITE,
+                radius,
+            },
+        }
+    }
+
+    #[test]
+    fn test_pair() {
+        let drawings = pair(
+            vec![
+                stroke(vec![Vec2::ZERO, Vec2::new(4.0, 0.0)], 1.0),
+                stroke(vec![Vec2::ONE], 3.0),
+            ],
+            vec![stroke(
+                vec![Vec2::ZERO, Vec2::new(0.0, 2.0), Vec2::new(0.0, 4.0)],
+                2.0,
+            )],
+            5.0,
+        );
+        assert_eq!(drawings.len(), 2);
+        assert_eq!(drawings[0].source_points.0.len(), 3);
+        assert_eq!(drawings[0].source_points.0[1], Vec2::new(2.0, 0.0));
+        assert_eq!(drawings[0].target_points.0.len(), 3);
+        assert_eq!(drawings[0].target_settings.radius, 2.0);
+        assert_eq!(drawings[0].layer, 5.0);
+        assert_eq!(drawings[1].target_points.0, vec![Vec2::ONE]);
+        assert_eq!(drawings[1].target_settings.radius, 3.0);
+        assert_eq!(drawings[1].layer, 6.0);
+    }
+}
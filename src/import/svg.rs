@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use usvg::tiny_skia_path::{self, PathSegment};
+
+use super::Stroke;
+use crate::points::{Points, PointsSettings};
+
+/// Curves are flattened into segments about this long before resampling
+const FLATTEN_LENGTH: f32 = 1.0;
+const MAX_CURVE_SEGMENTS: f32 = 256.0;
+/// Closest spacing between points of an imported stroke
+const MIN_SPACING: f32 = 0.5;
+
+/// Read every visible path of an SVG document as strokes, one per subpath,
+/// with the document centered on the origin.
+/// Paths without a stroke use the `default` settings, and their fill color if they have one.
+pub(crate) fn strokes(data: &[u8], default: PointsSettings) -> Result<Vec<Stroke>> {
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default()).context("Invalid SVG")?;
+    let center = Vec2::new(tree.size().width(), tree.size().height()) / 2.0;
+    let mut strokes = Vec::new();
+    collect_strokes(tree.root(), center, default, &mut strokes);
+    Ok(strokes)
+}
+
+fn collect_strokes(
+    group: &usvg::Group,
+    center: Vec2,
+    default: PointsSettings,
+    strokes: &mut Vec<Stroke>,
+) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(group) => collect_strokes(group, center, default, strokes),
+            usvg::Node::Path(path) if path.is_visible() => {
+                let transform = path.abs_transform();
+                let Some(data) = path.data().clone().transform(transform) else {
+                    continue;
+                };
+                let settings = settings(path, transform, default);
+                // Space points so the discs overlap into a continuous line
+                let spacing = (settings.radius / 2.0).max(MIN_SPACING);
+                for polyline in flatten(&data) {
+                    // SVG is y down, world space is y up
+                    let points = Points(
+                        polyline
+                            .into_iter()
+                            .map(|p| Vec2::new(p.x - center.x, center.y - p.y))
+                            .collect(),
+                    );
+                    let count = (points.length() / spacing).ceil() as usize + 1;
+                    strokes.push(Stroke {
+                        points: points.resample(count),
+                        settings,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn settings(
+    path: &usvg::Path,
+    transform: tiny_skia_path::Transform,
+    default: PointsSettings,
+) -> PointsSettings {
+    let paint_color = |paint: &usvg::Paint| match paint {
+        usvg::Paint::Color(color) => Some(Srgba::rgb_u8(color.red, color.green, color.blue).into()),
+        _ => None,
+    };
+    match path.stroke() {
+        Some(stroke) => {
+            let (scale_x, scale_y) = transform.get_scale();
+            PointsSettings {
+                color: paint_color(stroke.paint()).unwrap_or(default.color),
+                radius: stroke.width().get() * (scale_x * scale_y).sqrt() / 2.0,
+            }
+        }
+        None => PointsSettings {
+            color: path
+                .fill()
+                .and_then(|fill| paint_color(fill.paint()))
+                .unwrap_or(default.color),
+            radius: default.radius,
+        },
+    }
+}
+
+// Split a path into polylines, one per subpath
+fn flatten(path: &tiny_skia_path::Path) -> Vec<Vec<Vec2>> {
+    let mut polylines = Vec::new();
+    let mut polyline: Vec<Vec2> = Vec::new();
+    for segment in path.segments() {
+        let start = polyline.last().copied().unwrap_or_default();
+        match segment {
+            PathSegment::MoveTo(p) => {
+                if !polyline.is_empty() {
+                    polylines.push(std::mem::take(&mut polyline));
+                }
+                polyline.push(Vec2::new(p.x, p.y));
+            }
+            PathSegment::LineTo(p) => polyline.push(Vec2::new(p.x, p.y)),
+            PathSegment::QuadTo(p1, p) => {
+                let control = [start, Vec2::new(p1.x, p1.y), Vec2::new(p.x, p.y)];
+                let steps = curve_steps(&control);
+                polyline.extend((1..=steps).map(|i| {
+                    let t = i as f32 / steps as f32;
+                    let a = control[0].lerp(control[1], t);
+                    let b = control[1].lerp(control[2], t);
+                    a.lerp(b, t)
+                }));
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                let control = [
+                    start,
+                    Vec2::new(p1.x, p1.y),
+                    Vec2::new(p2.x, p2.y),
+                    Vec2::new(p.x, p.y),
+                ];
+                let steps = curve_steps(&control);
+                polyline.extend((1..=steps).map(|i| {
+                    let t = i as f32 / steps as f32;
+                    let a = control[0].lerp(control[1], t);
+                    let b = control[1].lerp(control[2], t);
+                    let c = control[2].lerp(control[3], t);
+                    a.lerp(b, t).lerp(b.lerp(c, t), t)
+                }));
+            }
+            PathSegment::Close => {
+                if let Some(first) = polyline.first().copied() {
+                    polyline.push(first);
+                }
+            }
+        }
+    }
+    if !polyline.is_empty() {
+        polylines.push(polyline);
+    }
+    polylines
+}
+
+// The control polygon is at least as long as the curve
+fn curve_steps(control: &[Vec2]) -> usize {
+    let length: f32 = control.windows(2).map(|w| w[0].distance(w[1])).sum();
+    (length / FLATTEN_LENGTH).clamp(1.0, MAX_CURVE_SEGMENTS) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT: PointsSettings = PointsSettings {
+        color: LinearRgba::WHITE,
+        radius: 3.0,
+    };
+
+    #[test]
+    fn test_stroked_path() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50">
+            <path d="M0 0 L10 0" stroke="#ff0000" stroke-width="4" fill="none"/>
+        </svg>"##;
+        let strokes = strokes(svg, DEFAULT).unwrap();
+        assert_eq!(strokes.len(), 1);
+        let stroke = &strokes[0];
+        assert_eq!(stroke.settings.radius, 2.0);
+        assert_eq!(stroke.settings.color, LinearRgba::RED);
+        // Centered and flipped, spaced by half the radius
+        assert_eq!(stroke.points.0.len(), 11);
+        assert_eq!(stroke.points.0[0], Vec2::new(-50.0, 25.0));
+        assert_eq!(stroke.points.0[10], Vec2::new(-40.0, 25.0));
+    }
+
+    #[test]
+    fn test_transformed_filled_shapes() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+            <g transform="scale(2)">
+                <rect x="0" y="0" width="10" height="10" fill="#00ff00"/>
+                <circle cx="25" cy="25" r="5" stroke="#0000ff" stroke-width="1"/>
+            </g>
+        </svg>"##;
+        let strokes = strokes(svg, DEFAULT).unwrap();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0].settings.radius, DEFAULT.radius);
+        assert_eq!(strokes[0].settings.color, LinearRgba::GREEN);
+        // Closed rectangle starts and ends at the corner
+        assert_eq!(strokes[0].points.0.first(), Some(&Vec2::new(-50.0, 50.0)));
+        assert_eq!(strokes[0].points.0.last(), Some(&Vec2::new(-50.0, 50.0)));
+        assert_eq!(strokes[1].settings.radius, 1.0);
+        // Flattened circle stays on its radius
+        for point in &strokes[1].points.0 {
+            assert!((point.length() - 10.0).abs() < 0.1, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn test_invalid_svg() {
+        assert!(strokes(b"not svg", DEFAULT).is_err());
+    }
+}
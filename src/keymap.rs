@@ -0,0 +1,568 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use bevy::{
+    input::{
+        ButtonState, InputSystem,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Deserializer, de};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_command("keys", "Show or hide the key bindings", &["F1"])
+        .add_systems(PreUpdate, read_typed.after(InputSystem))
+        .add_systems(PostStartup, check_keymap)
+        .add_systems(Update, toggle_help.run_if(command_just_pressed("keys")));
+}
+
+/// Overlay listing the commands and their keys
+#[derive(Component)]
+struct KeymapHelp;
+
+/// Named editor commands and the keys bound to them.
+/// Plugins register their commands with default bindings, which the user's config can replace.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct Keymap {
+    /// In registration order
+    commands: Vec<EditorCommand>,
+    /// Bindings from the config, by command name
+    overrides: HashMap<String, Vec<Binding>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct EditorCommand {
+    pub(crate) name: &'static str,
+    pub(crate) description: &'static str,
+    pub(crate) bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    pub(crate) fn new(overrides: HashMap<String, Bindings>) -> Self {
+        Self {
+            commands: Vec::new(),
+            overrides: overrides
+                .into_iter()
+                .map(|(name, bindings)| (name, bindings.0))
+                .collect(),
+        }
+    }
+
+    /// Add a command, bound to `defaults` (e.g. `["Ctrl+S"]`) unless the config binds it
+    pub(crate) fn register(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        defaults: &[&str],
+    ) {
+        if self.command(name).is_some() {
+            return;
+        }
+        let bindings = match self.overrides.get(name) {
+            Some(bindings) => bindings.clone(),
+            None => defaults
+                .iter()
+                .map(|binding| binding.parse().expect("valid default binding"))
+                .collect(),
+        };
+        self.commands.push(EditorCommand {
+            name,
+            description,
+            bindings,
+        });
+    }
+
+    pub(crate) fn commands(&self) -> &[EditorCommand] {
+        &self.commands
+    }
+
+    fn command(&self, name: &str) -> Option<&EditorCommand> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    /// Whether a binding of the command was just pressed, with exactly its modifiers held
+    pub(crate) fn just_pressed(
+        &self,
+        name: &str,
+        input: &ButtonInput<KeyCode>,
+        typed: &Typed,
+    ) -> bool {
+        let held = Modifiers::held(input);
+        self.command(name).is_some_and(|command| {
+            command
+                .bindings
+                .iter()
+                .any(|binding| binding.just_pressed(held, input, typed))
+        })
+    }
+
+    // Config bindings for commands that don't exist, e.g. misspelled
+    fn unknown(&self) -> Vec<&str> {
+        let mut unknown: Vec<&str> = self
+            .overrides
+            .keys()
+            .map(String::as_str)
+            .filter(|name| self.command(name).is_none())
+            .collect();
+        unknown.sort();
+        unknown
+    }
+
+    // Bindings shared by commands, which would run together
+    fn conflicts(&self) -> Vec<(Binding, &str, &str)> {
+        let mut conflicts = Vec::new();
+        for (index, command) in self.commands.iter().enumerate() {
+            for other in &self.commands[index + 1..] {
+                for binding in &command.bindings {
+                    if other.bindings.contains(binding) {
+                        conflicts.push((*binding, command.name, other.name));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Registers editor commands on apps
+pub(crate) trait RegisterCommand {
+    /// Add a command, see [`Keymap::register`]
+    fn register_command(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        defaults: &[&str],
+    ) -> &mut Self;
+}
+
+impl RegisterCommand for App {
+    fn register_command(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        defaults: &[&str],
+    ) -> &mut Self {
+        self.init_resource::<Keymap>().init_resource::<Typed>();
+        self.world_mut()
+            .resource_mut::<Keymap>()
+            .register(name, description, defaults);
+        self
+    }
+}
+
+/// Run condition for systems running a command, e.g. `command_just_pressed("save")`
+pub(crate) fn command_just_pressed(
+    name: &'static str,
+) -> impl FnMut(Res<Keymap>, Res<ButtonInput<KeyCode>>, Res<Typed>) -> bool + Clone {
+    move |keymap: Res<Keymap>, input: Res<ButtonInput<KeyCode>>, typed: Res<Typed>| {
+        keymap.just_pressed(name, &input, &typed)
+    }
+}
+
+/// Characters typed this frame, lowercase.
+/// Character bindings follow the keyboard layout, wherever the key is.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct Typed(Vec<char>);
+
+fn read_typed(mut typed: ResMut<Typed>, mut events: EventReader<KeyboardInput>) {
+    typed.0.clear();
+    for event in events.read() {
+        if let (ButtonState::Pressed, false, Key::Character(text)) =
+            (event.state, event.repeat, &event.logical_key)
+        {
+            typed.0.extend(text.chars().flat_map(char::to_lowercase));
+        }
+    }
+}
+
+// Report config mistakes once every plugin has registered its commands
+fn check_keymap(keymap: Res<Keymap>) {
+    for name in keymap.unknown() {
+        warn!("Ignoring key binding for unknown command \"{}\"", name);
+    }
+    for (binding, command, other) in keymap.conflicts() {
+        warn!(
+            "{} is bound to both \"{}\" and \"{}\"",
+            binding, command, other
+        );
+    }
+}
+
+fn toggle_help(keymap: Res<Keymap>, help: Query<Entity, With<KeymapHelp>>, mut commands: Commands) {
+    if let Ok(help) = help.get_single() {
+        commands.entity(help).despawn_recursive();
+        return;
+    }
+    commands
+        .spawn((
+            KeymapHelp,
+            Node {
+                position_type: PositionType::Absolute,
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::auto(2),
+                column_gap: Val::Px(20.0),
+                row_gap: Val::Px(6.0),
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.8)),
+            GlobalZIndex(1),
+        ))
+        .with_children(|parent| {
+            for command in keymap.commands() {
+                parent.spawn(Text::new(command.keys()));
+                parent.spawn(Text::new(command.description));
+            }
+        });
+}
+
+impl EditorCommand {
+    /// Bindings for people to read, e.g. "S, Ctrl+S"
+    fn keys(&self) -> String {
+        if self.bindings.is_empty() {
+            return "unbound".into();
+        }
+        self.bindings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Modifiers {
+    pub(crate) ctrl: bool,
+    pub(crate) shift: bool,
+    pub(crate) alt: bool,
+    /// Command on macOS, Windows key elsewhere
+    pub(crate) super_key: bool,
+}
+
+impl Modifiers {
+    fn held(input: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            ctrl: input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+            super_key: input.any_pressed([KeyCode::SuperLeft, KeyCode::SuperRight]),
+        }
+    }
+}
+
+/// Key with modifiers, written e.g. "Ctrl+Shift+S"
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Binding {
+    pub(crate) key: BindingKey,
+    pub(crate) modifiers: Modifiers,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum BindingKey {
+    /// Key typing a character in the user's layout, lowercase
+    Character(char),
+    /// Key that doesn't type a character, e.g. Space or F1
+    Code(KeyCode),
+}
+
+impl Binding {
+    fn just_pressed(&self, held: Modifiers, input: &ButtonInput<KeyCode>, typed: &Typed) -> bool {
+        match self.key {
+            BindingKey::Code(key) => self.modifiers == held && input.just_pressed(key),
+            BindingKey::Character(character) => {
+                // Characters without case may need Shift to type, e.g. "?", so it only counts for letters
+                let cased = character.is_lowercase();
+                let held = Modifiers {
+                    shift: held.shift && cased,
+                    ..held
+                };
+                let modifiers = Modifiers {
+                    shift: self.modifiers.shift && cased,
+                    ..self.modifiers
+                };
+                modifiers == held && typed.0.contains(&character)
+            }
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (modifier_names, key_name) = value.rsplit_once('+').unwrap_or(("", value));
+        let mut modifiers = Modifiers::default();
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            let modifier = match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "shift" => &mut modifiers.shift,
+                "alt" | "option" => &mut modifiers.alt,
+                "super" | "cmd" | "command" | "meta" => &mut modifiers.super_key,
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", name, value)),
+            };
+            *modifier = true;
+        }
+        let key = key_from_name(key_name)
+            .ok_or_else(|| format!("unknown key \"{}\" in \"{}\"", key_name, value))?;
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Modifiers {
+            ctrl,
+            shift,
+            alt,
+            super_key,
+        } = self.modifiers;
+        for (held, name) in [
+            (ctrl, "Ctrl"),
+            (shift, "Shift"),
+            (alt, "Alt"),
+            (super_key, "Super"),
+        ] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.key {
+            BindingKey::Character(character) => write!(f, "{}", character.to_uppercase()),
+            BindingKey::Code(key) => match key_name(key) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "{:?}", key),
+            },
+        }
+    }
+}
+
+/// A command's bindings in the config, one binding or a list (empty to unbind)
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Bindings(pub(crate) Vec<Binding>);
+
+impl<'de> Deserialize<'de> for Bindings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(String),
+            Many(Vec<String>),
+        }
+        let names = match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(name) => vec![name],
+            OneOrMany::Many(names) => names,
+        };
+        names
+            .iter()
+            .map(|name| name.parse().map_err(de::Error::custom))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Names of keys that don't type a character, other keys are named by their character.
+/// Names after the first for a key are aliases.
+const KEYS: &[(&str, KeyCode)] = &[
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Escape", KeyCode::Escape),
+    ("Esc", KeyCode::Escape),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+];
+
+/// Key from its name ignoring case, or the character it types
+fn key_from_name(name: &str) -> Option<BindingKey> {
+    let mut characters = name.chars();
+    match (characters.next(), characters.next()) {
+        (Some(character), None) if !character.is_whitespace() && !character.is_control() => {
+            // Lowercase to one character, e.g. not "İ"
+            let mut lowercase = character.to_lowercase();
+            match (lowercase.next(), lowercase.next()) {
+                (Some(lowercase), None) => Some(BindingKey::Character(lowercase)),
+                _ => Some(BindingKey::Character(character)),
+            }
+        }
+        _ => KEYS
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+            .map(|(_, key)| BindingKey::Code(*key)),
+    }
+}
+
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEYS.iter()
+        .find(|(_, named)| *named == key)
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(value: &str) -> Binding {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_binding() {
+        assert_eq!(
+            binding("s"),
+            Binding {
+                key: BindingKey::Character('s'),
+                modifiers: default(),
+            }
+        );
+        assert_eq!(
+            binding("Ctrl+Shift+z"),
+            Binding {
+                key: BindingKey::Character('z'),
+                modifiers: Modifiers {
+                    ctrl: true,
+                    shift: true,
+                    ..default()
+                },
+            }
+        );
+        assert_eq!(binding("cmd+space").to_string(), "Super+Space");
+        assert_eq!(binding("esc").to_string(), "Escape");
+        assert_eq!(binding("F12").key, BindingKey::Code(KeyCode::F12));
+        assert_eq!(binding("/").key, BindingKey::Character('/'));
+        assert_eq!(binding("Alt+É").to_string(), "Alt+É");
+        assert!("Hyper+S".parse::<Binding>().is_err());
+        assert!("Ctrl+".parse::<Binding>().is_err());
+        assert!("".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn test_keymap() {
+        let overrides = HashMap::from([
+            ("save".to_string(), Bindings(vec![binding("F2")])),
+            ("undo".to_string(), Bindings(vec![binding("U")])),
+            ("unknown".to_string(), Bindings(vec![])),
+        ]);
+        let mut keymap = Keymap::new(overrides);
+        keymap.register("save", "Save", &["Ctrl+S"]);
+        keymap.register("save-as", "Save As", &["Ctrl+Shift+S", "F2"]);
+        keymap.register("undo", "Undo", &["Ctrl+Z"]);
+        keymap.register("undo", "Undo again", &["Z"]);
+
+        assert_eq!(keymap.commands().len(), 3);
+        assert_eq!(keymap.commands()[0].bindings, vec![binding("F2")]);
+        assert_eq!(keymap.commands()[2].bindings, vec![binding("U")]);
+        assert_eq!(keymap.commands()[1].keys(), "Ctrl+Shift+S, F2");
+        assert_eq!(keymap.unknown(), vec!["unknown"]);
+        assert_eq!(keymap.conflicts(), vec![(binding("F2"), "save", "save-as")]);
+
+        // Modifiers must match exactly
+        let mut input = ButtonInput::default();
+        input.press(KeyCode::ShiftLeft);
+        input.press(KeyCode::ControlRight);
+        input.press(KeyCode::KeyS);
+        let typed = Typed(vec!['s']);
+        assert!(keymap.just_pressed("save-as", &input, &typed));
+        assert!(!keymap.just_pressed("save", &input, &typed));
+        assert!(!keymap.just_pressed("missing", &input, &typed));
+        input.release(KeyCode::ShiftLeft);
+        assert!(!keymap.just_pressed("save-as", &input, &typed));
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut keymap = Keymap::default();
+        keymap.register("undo", "Undo", &["Ctrl+Z"]);
+        keymap.register("help", "Help", &["?"]);
+
+        // On AZERTY Z is where QWERTY has W
+        let mut input = ButtonInput::default();
+        input.press(KeyCode::ControlLeft);
+        input.press(KeyCode::KeyW);
+        assert!(keymap.just_pressed("undo", &input, &Typed(vec!['z'])));
+        input.release(KeyCode::KeyW);
+        input.press(KeyCode::KeyZ);
+        assert!(!keymap.just_pressed("undo", &input, &Typed(vec!['w'])));
+
+        // Shift is needed to type some characters
+        let mut input = ButtonInput::default();
+        input.press(KeyCode::ShiftRight);
+        input.press(KeyCode::Slash);
+        assert!(keymap.just_pressed("help", &input, &Typed(vec!['?'])));
+        input.release(KeyCode::ShiftRight);
+        input.press(KeyCode::KeyM);
+        assert!(keymap.just_pressed("help", &input, &Typed(vec!['?'])));
+    }
+
+    #[test]
+    fn test_command_just_pressed() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .register_command("play", "Play", &["Space"])
+            .init_resource::<Count>()
+            .add_systems(
+                Update,
+                (|mut count: ResMut<Count>| count.0 += 1).run_if(command_just_pressed("play")),
+            );
+
+        app.update();
+        assert_eq!(app.world().resource::<Count>().0, 0);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::Space);
+        app.update();
+        assert_eq!(app.world().resource::<Count>().0, 1);
+    }
+
+    #[test]
+    fn test_read_typed() {
+        let mut app = App::new();
+        app.init_resource::<Typed>()
+            .add_event::<KeyboardInput>()
+            .add_systems(Update, read_typed);
+        for (logical_key, state, repeat) in [
+            (Key::Character("Z".into()), ButtonState::Pressed, false),
+            (Key::Character("a".into()), ButtonState::Pressed, true),
+            (Key::Character("b".into()), ButtonState::Released, false),
+            (Key::Space, ButtonState::Pressed, false),
+        ] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::KeyW,
+                logical_key,
+                state,
+                repeat,
+                window: Entity::PLACEHOLDER,
+            });
+        }
+        app.update();
+        assert_eq!(app.world().resource::<Typed>().0, vec!['z']);
+        app.update();
+        assert!(app.world().resource::<Typed>().0.is_empty());
+    }
+
+    #[derive(Resource, Default)]
+    struct Count(usize);
+}
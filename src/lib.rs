@@ -2,11 +2,22 @@ use anyhow::Result;
 use bevy::prelude::*;
 mod animation;
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+mod autosave;
 mod camera;
 pub mod cli;
+mod config;
 mod draw;
+#[cfg(not(target_arch = "wasm32"))]
+mod drop;
+mod export;
+#[cfg(not(target_arch = "wasm32"))]
+mod files;
+mod import;
+mod keymap;
 mod points;
 mod project;
+mod render;
 mod ui;
 mod util;
 #[cfg(target_arch = "wasm32")]
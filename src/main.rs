@@ -1,6 +1,6 @@
 use terp::cli;
 
 #[cfg(not(target_arch = "wasm32"))]
-fn main() {
-    cli::parse_cli().run();
+fn main() -> bevy::app::AppExit {
+    cli::parse_cli().run()
 }
@@ -13,6 +13,16 @@ pub(crate) struct PointsSettings {
     pub(crate) radius: f32,
 }
 
+impl PointsSettings {
+    /// Settings moved toward `target` by `t`, as `points.wgsl` mixes them
+    pub(crate) fn mix(&self, target: &PointsSettings, t: f32) -> PointsSettings {
+        PointsSettings {
+            color: self.color.mix(&target.color, t),
+            radius: self.radius.lerp(target.radius, t),
+        }
+    }
+}
+
 #[derive(Asset, TypePath, AsBindGroup, Debug, Default, Copy, Clone)]
 pub(crate) struct PointsMaterial {
     #[uniform(0)]
@@ -83,6 +93,21 @@ mod tests {
         assert_eq!(settings.radius, 0.0);
     }
 
+    #[test]
+    fn test_points_settings_mix() {
+        let source = PointsSettings {
+            color: LinearRgba::BLACK,
+            radius: 2.0,
+        };
+        let target = PointsSettings {
+            color: LinearRgba::WHITE,
+            radius: 4.0,
+        };
+        let mixed = source.mix(&target, 0.5);
+        assert_eq!(mixed.color, LinearRgba::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(mixed.radius, 3.0);
+    }
+
     #[test]
     fn test_points_settings_clone() {
         let settings = PointsSettings {
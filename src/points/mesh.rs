@@ -9,6 +9,111 @@ use serde::{Deserialize, Serialize};
 pub(crate) struct Points(pub Vec<Vec2>);
 
 impl Points {
+    /// Points moved toward `target` by `t`, as `points.wgsl` mixes positions
+    pub(crate) fn mix(&self, target: &Points, t: f32) -> Points {
+        Points(
+            self.0
+                .iter()
+                .zip(&target.0)
+                .map(|(source, target)| source.lerp(*target, t))
+                .collect(),
+        )
+    }
+
+    /// Bounds of the point centers
+    pub(crate) fn bounds(&self) -> Option<Rect> {
+        let first = *self.0.first()?;
+        Some(
+            self.0
+                .iter()
+                .fold(Rect::from_corners(first, first), |rect, p| {
+                    rect.union_point(*p)
+                }),
+        )
+    }
+
+    /// Length of the polyline through the points
+    pub(crate) fn length(&self) -> f32 {
+        self.0.windows(2).map(|w| w[0].distance(w[1])).sum()
+    }
+
+    /// Distance from `point` to the polyline through the points, none if there are no points
+    pub(crate) fn distance(&self, point: Vec2) -> Option<f32> {
+        match self.0.as_slice() {
+            [] => None,
+            [only] => Some(only.distance(point)),
+            points => points
+                .windows(2)
+                .map(|w| segment_distance(point, w[0], w[1]))
+                .reduce(f32::min),
+        }
+    }
+
+    /// `count` points evenly spaced along the polyline through the points
+    pub(crate) fn resample(&self, count: usize) -> Points {
+        let (Some(first), Some(last)) = (self.0.first(), self.0.last()) else {
+            return Points(Vec::new());
+        };
+        let length = self.length();
+        if count < 2 || length == 0.0 {
+            return Points(vec![*first; count]);
+        }
+
+        let step = length / (count - 1) as f32;
+        let mut result = Vec::with_capacity(count);
+        result.push(*first);
+        let mut segments = self.0.windows(2);
+        // Distance along the polyline to the start of the current segment
+        let mut start = 0.0;
+        let mut segment = segments.next();
+        for i in 1..count - 1 {
+            let distance = step * i as f32;
+            while let Some([a, b]) = segment {
+                let segment_length = a.distance(*b);
+                if start + segment_length >= distance && segment_length > 0.0 {
+                    result.push(a.lerp(*b, (distance - start) / segment_length));
+                    break;
+                }
+                start += segment_length;
+                segment = segments.next();
+            }
+        }
+        // Rounding may have run off the end of the polyline
+        result.resize(count, *last);
+        result[count - 1] = *last;
+        Points(result)
+    }
+
+    /// Drop points within `tolerance` of the stroke through the remaining points
+    /// (Ramer-Douglas-Peucker), the ends are always kept.
+    /// Each point is drawn as a disc, so points are also kept to leave no more than `spacing`
+    /// between them (unless they were further apart to begin with), or strokes would break up.
+    /// Source and target points are simplified together so they still pair up,
+    /// a point is kept on both sides if either side needs it.
+    /// Points that don't pair up are left alone.
+    pub(crate) fn simplify_paired(
+        &self,
+        target: &Points,
+        tolerance: f32,
+        spacing: f32,
+    ) -> (Points, Points) {
+        if self.0.len() != target.0.len() {
+            return (self.clone(), target.clone());
+        }
+        let keep = significant(&[self, target], tolerance, spacing);
+        (self.keep(&keep), target.keep(&keep))
+    }
+
+    fn keep(&self, keep: &[bool]) -> Points {
+        Points(
+            self.0
+                .iter()
+                .zip(keep)
+                .filter_map(|(point, keep)| keep.then_some(*point))
+                .collect(),
+        )
+    }
+
     pub(crate) fn append(mesh: &mut Mesh, point: Vec2) {
         if let Some(VertexAttributeValues::Float32x3(positions)) =
             mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
@@ -117,6 +222,59 @@ impl TryFrom<&VertexAttributeValues> for Points {
     }
 }
 
+// Which points of equal length strokes are further than `tolerance` from the simplified strokes,
+// or needed to keep points within `spacing` of each other
+fn significant(strokes: &[&Points], tolerance: f32, spacing: f32) -> Vec<bool> {
+    let len = strokes.first().map_or(0, |stroke| stroke.0.len());
+    let mut keep = vec![false; len];
+    if len == 0 {
+        return keep;
+    }
+    keep[0] = true;
+    keep[len - 1] = true;
+    let mut ranges = vec![(0, len - 1)];
+    while let Some((start, end)) = ranges.pop() {
+        let farthest = (start + 1..end)
+            .map(|index| {
+                let distance = strokes
+                    .iter()
+                    .map(|stroke| segment_distance(stroke.0[index], stroke.0[start], stroke.0[end]))
+                    .fold(0.0, f32::max);
+                (index, distance)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((farthest, distance)) = farthest else {
+            continue;
+        };
+        let index = if distance > tolerance {
+            farthest
+        } else if strokes
+            .iter()
+            .any(|stroke| stroke.0[start].distance(stroke.0[end]) > spacing)
+        {
+            (start + end) / 2
+        } else {
+            continue;
+        };
+        keep[index] = true;
+        ranges.push((start, index));
+        ranges.push((index, end));
+    }
+    keep
+}
+
+// Distance from `point` to the segment from `a` to `b`.
+// Not the infinite line, so closed strokes ending where they started still simplify.
+fn segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
 pub(crate) trait PointsMeshBuilder {
     fn empty() -> Mesh;
     fn build(points: Option<&Points>) -> Mesh;
@@ -237,6 +395,127 @@ mod tests {
         assert_eq!(target.0, vec![Vec2::new(5.0, 6.0), Vec2::new(7.0, 8.0)]);
     }
 
+    #[test]
+    fn test_points_mix() {
+        let source = Points(vec![Vec2::new(0.0, 0.0), Vec2::new(2.0, 2.0)]);
+        let target = Points(vec![Vec2::new(4.0, 8.0), Vec2::new(2.0, -2.0)]);
+        assert_eq!(
+            source.mix(&target, 0.25).0,
+            vec![Vec2::new(1.0, 2.0), Vec2::new(2.0, 1.0)]
+        );
+        assert_eq!(source.mix(&target, 1.0).0, target.0);
+    }
+
+    #[test]
+    fn test_points_bounds() {
+        assert!(Points(vec![]).bounds().is_none());
+        let points = Points(vec![Vec2::new(1.0, -2.0), Vec2::new(-3.0, 4.0)]);
+        assert_eq!(points.bounds(), Some(Rect::new(-3.0, -2.0, 1.0, 4.0)));
+    }
+
+    #[test]
+    fn test_points_distance() {
+        assert_eq!(Points(vec![]).distance(Vec2::ZERO), None);
+        assert_eq!(Points(vec![Vec2::X]).distance(Vec2::ZERO), Some(1.0));
+        let points = Points(vec![
+            Vec2::ZERO,
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+        ]);
+        assert_eq!(points.distance(Vec2::new(5.0, 3.0)), Some(3.0));
+        assert_eq!(points.distance(Vec2::new(12.0, 5.0)), Some(2.0));
+        assert_eq!(points.distance(Vec2::new(-4.0, 3.0)), Some(5.0));
+    }
+
+    #[test]
+    fn test_points_resample() {
+        let points = Points(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 3.0),
+        ]);
+        assert_eq!(points.length(), 6.0);
+        assert_eq!(
+            points.resample(4).0,
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(3.0, 1.0),
+                Vec2::new(3.0, 3.0)
+            ]
+        );
+        assert_eq!(points.resample(1).0, vec![Vec2::ZERO]);
+        assert_eq!(points.resample(9).0.len(), 9);
+        assert!(Points(vec![]).resample(3).0.is_empty());
+        assert_eq!(
+            Points(vec![Vec2::ONE]).resample(2).0,
+            vec![Vec2::ONE, Vec2::ONE]
+        );
+    }
+
+    fn simplify(points: &Points, tolerance: f32) -> Points {
+        points.simplify_paired(points, tolerance, f32::INFINITY).0
+    }
+
+    #[test]
+    fn test_points_simplify() {
+        let points = Points(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.1),
+            Vec2::new(2.0, -0.1),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 5.0),
+            Vec2::new(3.0, 10.0),
+        ]);
+        assert_eq!(
+            simplify(&points, 0.5).0,
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(3.0, 0.0),
+                Vec2::new(3.0, 10.0)
+            ]
+        );
+        assert_eq!(simplify(&points, 0.05).0.len(), 5);
+        assert!(simplify(&Points(vec![]), 1.0).0.is_empty());
+        assert_eq!(simplify(&Points(vec![Vec2::ONE]), 1.0).0, vec![Vec2::ONE]);
+
+        // Closed strokes keep their shape
+        let square = Points(vec![
+            Vec2::ZERO,
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::ZERO,
+        ]);
+        assert_eq!(simplify(&square, 1.0).0.len(), 5);
+    }
+
+    #[test]
+    fn test_points_simplify_paired() {
+        let source = Points(vec![Vec2::ZERO, Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)]);
+        let target = Points(vec![Vec2::ZERO, Vec2::new(1.0, 5.0), Vec2::new(2.0, 0.0)]);
+        // The middle point only matters to the target, but is kept on both sides
+        let (simple_source, simple_target) = source.simplify_paired(&target, 1.0, 10.0);
+        assert_eq!(simple_source.0, source.0);
+        assert_eq!(simple_target.0, target.0);
+
+        let (simple_source, simple_target) = source.simplify_paired(&source, 1.0, 10.0);
+        assert_eq!(simple_source.0.len(), 2);
+        assert_eq!(simple_target.0.len(), 2);
+
+        // Unpaired points are not simplified
+        let (simple_source, _) = source.simplify_paired(&Points(vec![Vec2::ZERO]), 1.0, 10.0);
+        assert_eq!(simple_source.0.len(), 3);
+
+        // Straight strokes keep enough points for their discs to overlap
+        let line = Points((0..=10).map(|x| Vec2::new(x as f32, 0.0)).collect());
+        let (simple_line, _) = line.simplify_paired(&line, 1.0, 3.0);
+        assert_eq!(
+            simple_line.0,
+            [0.0, 2.0, 5.0, 7.0, 10.0].map(|x| Vec2::new(x, 0.0))
+        );
+    }
+
     #[test]
     fn test_padding() {
         assert_eq!(
@@ -1,47 +1,124 @@
 use std::{
-    fs::{self, OpenOptions},
-    io::Write,
+    fs,
+    path::{Path, PathBuf},
 };
 
 use crate::{
-    animation::Animatable,
+    Interpolated,
+    animation::{self, Animatable},
     cli, error_handler,
+    keymap::{RegisterCommand, command_just_pressed},
     points::{Points, PointsMaterial, PointsMeshBuilder, PointsSettings},
 };
-use anyhow::Result;
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use anyhow::{Context, Result};
+use bevy::{ecs::system::SystemParam, prelude::*};
 use serde::{Deserialize, Serialize};
 
+mod format;
+mod write;
+
+pub(crate) use format::Format;
+pub(crate) use write::BACKUPS;
+
 pub(super) fn plugin(app: &mut App) {
-    app.add_event::<LoadProjectData>()
+    app.init_resource::<ProjectSettings>()
+        .init_resource::<write::Writer>()
+        .register_command("save", "Save the project", &["S", "Ctrl+S"])
+        .add_event::<LoadProjectData>()
         .add_event::<LoadProject>()
         .add_event::<SaveProjectData>()
-        .add_systems(Startup, read_project.pipe(error_handler))
+        .add_event::<ProjectSaved>()
+        .add_event::<ImportSvg>()
+        .add_systems(
+            Startup,
+            (
+                read_project.pipe(error_handler),
+                read_imports.pipe(error_handler),
+            ),
+        )
         .add_systems(
             Update,
             (
                 load_project_data.pipe(error_handler),
+                load_settings,
                 save_project
                     .pipe(error_handler)
-                    .run_if(input_just_pressed(KeyCode::KeyS)),
-                save_project_data.pipe(error_handler),
+                    .run_if(command_just_pressed("save")),
+                write::save_project_data.pipe(error_handler),
             ),
+        )
+        .add_systems(
+            Last,
+            write::flush_on_exit
+                .pipe(error_handler)
+                .run_if(on_event::<AppExit>),
         );
 }
 
 pub(super) fn player_plugin(app: &mut App) {
-    app.add_event::<LoadProjectData>()
+    app.init_resource::<ProjectSettings>()
+        .add_event::<LoadProjectData>()
         .add_event::<LoadProject>()
         .add_systems(Startup, read_project.pipe(error_handler))
-        .add_systems(Update, load_project_data.pipe(error_handler));
+        .add_systems(
+            Update,
+            (load_project_data.pipe(error_handler), load_settings),
+        );
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub(crate) struct Project {
+    pub(crate) settings: ProjectSettings,
     pub(crate) drawings: Vec<Drawing>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Project wide settings, so a project plays back the same wherever it is opened
+#[derive(Resource, Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ProjectSettings {
+    /// Size of the area shown, centered on the origin.
+    /// The player and editor scale it to fit their windows.
+    pub(crate) canvas: Vec2,
+    pub(crate) background: LinearRgba,
+    /// Seconds to animate from source to target, the animation then plays back to source
+    pub(crate) duration: f32,
+    pub(crate) easing: EaseFunction,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            canvas: Vec2::splat(600.0),
+            // Bevy's default clear color
+            background: Color::srgb_u8(43, 44, 47).into(),
+            duration: animation::DURATION,
+            easing: animation::EASING,
+        }
+    }
+}
+
+impl ProjectSettings {
+    /// World space area of the canvas
+    pub(crate) fn canvas_rect(&self) -> Rect {
+        Rect::from_center_size(Vec2::ZERO, self.canvas)
+    }
+
+    /// Describe each setting that can't be edited or animated
+    pub(crate) fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(self.canvas.cmpgt(Vec2::ZERO).all() && self.canvas.is_finite()) {
+            problems.push(format!(
+                "canvas size {}x{} is not positive",
+                self.canvas.x, self.canvas.y
+            ));
+        }
+        if !(self.duration > 0.0 && self.duration.is_finite()) {
+            problems.push(format!("duration {} is not positive", self.duration));
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct Drawing {
     pub(crate) source_settings: PointsSettings,
     pub(crate) target_settings: PointsSettings,
@@ -50,14 +127,96 @@ pub(crate) struct Drawing {
     pub(crate) layer: f32,
 }
 
+impl Project {
+    /// Read a project file in any format
+    pub(crate) fn load(path: &Path) -> Result<Project> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Project::from_bytes(&data).with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    /// Write a project file, in the format chosen by its extension
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        self.save_format(path, Format::for_save(path)?)
+    }
+
+    /// Write a project file in `format`, without a backup.
+    /// Command line outputs are only backed up when they overwrite the project they were made from.
+    pub(crate) fn save_format(&self, path: &Path, format: Format) -> Result<()> {
+        write::write(path, &self.to_bytes(format)?, 0)
+    }
+
+    /// Write a project file, in the format chosen by its extension, keeping `backups` previous versions
+    pub(crate) fn save_with_backups(&self, path: &Path, backups: usize) -> Result<()> {
+        write::write(path, &self.to_bytes(Format::for_save(path)?)?, backups)
+    }
+
+    /// Drawings in render order, back to front by layer
+    pub(crate) fn layered(&self) -> Vec<&Drawing> {
+        let mut drawings: Vec<&Drawing> = self.drawings.iter().collect();
+        drawings.sort_by(|a, b| a.layer.total_cmp(&b.layer));
+        drawings
+    }
+
+    /// Bounds containing every drawing at any interpolation
+    pub(crate) fn bounds(&self) -> Option<Rect> {
+        self.drawings
+            .iter()
+            .filter_map(Drawing::bounds)
+            .reduce(|a, b| a.union(b))
+    }
+}
+
+impl Drawing {
+    /// Points and settings at interpolation `t`, mixed the same way as `points.wgsl`
+    pub(crate) fn interpolate(&self, t: f32) -> (Points, PointsSettings) {
+        (
+            self.source_points.mix(&self.target_points, t),
+            self.source_settings.mix(&self.target_settings, t),
+        )
+    }
+
+    /// Bounds of the source and target points including their radius.
+    /// Every interpolation of the drawing lies within these bounds.
+    pub(crate) fn bounds(&self) -> Option<Rect> {
+        let source = self
+            .source_points
+            .bounds()
+            .map(|r| r.inflate(self.source_settings.radius));
+        let target = self
+            .target_points
+            .bounds()
+            .map(|r| r.inflate(self.target_settings.radius));
+        match (source, target) {
+            (Some(source), Some(target)) => Some(source.union(target)),
+            (source, target) => source.or(target),
+        }
+    }
+}
+
 #[derive(Event, Default, Debug)]
 pub(crate) struct LoadProjectData(pub(crate) Vec<u8>);
 
 #[derive(Event)]
 pub(crate) struct LoadProject(pub(crate) Project);
 
-#[derive(Event, Default, Debug)]
-pub(crate) struct SaveProjectData(pub(crate) Vec<u8>);
+/// Encoded project to write to `path` in the background, keeping `backups` previous versions
+#[derive(Event, Debug)]
+pub(crate) struct SaveProjectData {
+    pub(crate) path: PathBuf,
+    pub(crate) data: Vec<u8>,
+    pub(crate) backups: usize,
+}
+
+/// Sent once a `SaveProjectData` has been written
+#[derive(Event, Debug)]
+pub(crate) struct ProjectSaved(pub(crate) SaveProjectData);
+
+/// SVG document to import as drawings on one side
+#[derive(Event, Debug)]
+pub(crate) struct ImportSvg {
+    pub(crate) interpolated: Interpolated,
+    pub(crate) data: Vec<u8>,
+}
 
 fn read_project(args: Res<cli::Args>, mut commands: Commands) -> Result<()> {
     let Some(path) = args.project() else {
@@ -66,7 +225,19 @@ fn read_project(args: Res<cli::Args>, mut commands: Commands) -> Result<()> {
     if !path.exists() {
         return Ok(());
     }
-    commands.send_event(LoadProjectData(fs::read(path)?));
+    // Load immediately so the project is in place before any imports
+    commands.send_event(LoadProject(Project::load(path)?));
+    Ok(())
+}
+
+fn read_imports(args: Res<cli::Args>, mut commands: Commands) -> Result<()> {
+    for interpolated in [Interpolated::Source, Interpolated::Target] {
+        if let Some(path) = args.svg(interpolated) {
+            let data =
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            commands.send_event(ImportSvg { interpolated, data });
+        }
+    }
     Ok(())
 }
 
@@ -75,53 +246,80 @@ fn load_project_data(
     mut commands: Commands,
 ) -> Result<()> {
     if let Some(event) = events.read().last() {
-        let reader = flexbuffers::Reader::get_root(event.0.as_slice())?;
-        let project = Project::deserialize(reader)?;
+        let project = Project::from_bytes(event.0.as_slice())?;
         commands.send_event(LoadProject(project));
     }
     Ok(())
 }
 
-fn save_project(
-    mut commands: Commands,
-    entities: Query<(&MeshMaterial2d<PointsMaterial>, &Mesh2d, &Transform), With<Animatable>>,
-    materials: Res<Assets<PointsMaterial>>,
-    meshes: Res<Assets<Mesh>>,
-) -> Result<()> {
-    let drawings: Vec<Drawing> = entities
-        .iter()
-        .filter_map(|(material2d, mesh2d, transform)| -> Option<Drawing> {
-            let material = materials.get(material2d)?;
-            let mesh = meshes.get(mesh2d)?;
-            let (source_points, target_points) = mesh.to_points().ok()?;
-            Some(Drawing {
-                source_settings: material.source_settings,
-                target_settings: material.target_settings,
-                source_points,
-                target_points,
-                layer: transform.translation.z,
-            })
+fn load_settings(mut events: EventReader<LoadProject>, mut settings: ResMut<ProjectSettings>) {
+    if let Some(LoadProject(project)) = events.read().last() {
+        *settings = project.settings;
+    }
+}
+
+/// The project being edited
+#[derive(SystemParam)]
+pub(crate) struct CurrentProject<'w, 's> {
+    settings: Res<'w, ProjectSettings>,
+    #[allow(clippy::type_complexity)]
+    entities: Query<
+        'w,
+        's,
+        (
+            &'static MeshMaterial2d<PointsMaterial>,
+            &'static Mesh2d,
+            &'static Transform,
+        ),
+        With<Animatable>,
+    >,
+    materials: Res<'w, Assets<PointsMaterial>>,
+    meshes: Res<'w, Assets<Mesh>>,
+}
+
+impl CurrentProject<'_, '_> {
+    /// Save the project to `path`, in the format chosen by its extension
+    pub(crate) fn save(&self, path: &Path) -> Result<SaveProjectData> {
+        Ok(SaveProjectData {
+            path: path.into(),
+            data: self.project().to_bytes(Format::for_save(path)?)?,
+            backups: BACKUPS,
         })
-        .collect();
-    let project = Project { drawings };
-    let mut serializer = flexbuffers::FlexbufferSerializer::new();
-    project.serialize(&mut serializer)?;
-    commands.send_event(SaveProjectData(serializer.view().into()));
-    Ok(())
+    }
+
+    pub(crate) fn project(&self) -> Project {
+        let drawings: Vec<Drawing> = self
+            .entities
+            .iter()
+            .filter_map(|(material2d, mesh2d, transform)| -> Option<Drawing> {
+                let material = self.materials.get(material2d)?;
+                let mesh = self.meshes.get(mesh2d)?;
+                let (source_points, target_points) = mesh.to_points().ok()?;
+                Some(Drawing {
+                    source_settings: material.source_settings,
+                    target_settings: material.target_settings,
+                    source_points,
+                    target_points,
+                    layer: transform.translation.z,
+                })
+            })
+            .collect();
+        Project {
+            settings: *self.settings,
+            drawings,
+        }
+    }
 }
 
-fn save_project_data(args: Res<cli::Args>, mut events: EventReader<SaveProjectData>) -> Result<()> {
+fn save_project(
+    args: Res<cli::Args>,
+    current: CurrentProject,
+    mut commands: Commands,
+) -> Result<()> {
     let Some(path) = args.project() else {
         return Ok(());
     };
-    if let Some(event) = events.read().last() {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(path)?;
-        file.write_all(&event.0)?;
-    }
+    commands.send_event(current.save(path)?);
     Ok(())
 }
 
@@ -134,6 +332,11 @@ mod tests {
         let mut app = App::new();
         app.init_resource::<Assets<PointsMaterial>>()
             .init_resource::<Assets<Mesh>>()
+            .insert_resource(cli::Args::new(Some("test.terp")))
+            .insert_resource(ProjectSettings {
+                duration: 1.0,
+                ..default()
+            })
             .add_event::<SaveProjectData>()
             .add_systems(Update, save_project.pipe(error_handler));
 
@@ -170,9 +373,10 @@ mod tests {
 
         let mut event_cursor = events.get_cursor();
         let event = event_cursor.read(events).last().unwrap();
-        let reader = flexbuffers::Reader::get_root(event.0.as_slice()).unwrap();
-        let deserialized_project = Project::deserialize(reader).unwrap();
+        assert_eq!(event.path, Path::new("test.terp"));
+        let deserialized_project = Project::from_bytes(event.data.as_slice()).unwrap();
 
+        assert_eq!(deserialized_project.settings.duration, 1.0);
         assert_eq!(deserialized_project.drawings.len(), 1);
         let drawing = &deserialized_project.drawings[0];
 
@@ -180,4 +384,43 @@ mod tests {
         assert_eq!(drawing.target_points.0, vec![Vec2::new(1.0, 1.0)]);
         assert_eq!(drawing.layer, 1.0);
     }
+
+    fn drawing(layer: f32, source: Vec2, target: Vec2) -> Drawing {
+        Drawing {
+            source_settings: PointsSettings {
+                color: LinearRgba::BLACK,
+                radius: 1.0,
+            },
+            target_settings: PointsSettings {
+                color: LinearRgba::WHITE,
+                radius: 3.0,
+            },
+            source_points: Points(vec![source]),
+            target_points: Points(vec![target]),
+            layer,
+        }
+    }
+
+    #[test]
+    fn test_drawing_interpolate() {
+        let drawing = drawing(1.0, Vec2::ZERO, Vec2::new(10.0, -10.0));
+        let (points, settings) = drawing.interpolate(0.5);
+        assert_eq!(points.0, vec![Vec2::new(5.0, -5.0)]);
+        assert_eq!(settings.radius, 2.0);
+    }
+
+    #[test]
+    fn test_project_bounds() {
+        let project = Project {
+            drawings: vec![
+                drawing(2.0, Vec2::ZERO, Vec2::new(10.0, 0.0)),
+                drawing(1.0, Vec2::new(0.0, -10.0), Vec2::ZERO),
+            ],
+            ..default()
+        };
+        assert_eq!(project.bounds(), Some(Rect::new(-3.0, -11.0, 13.0, 3.0)));
+        let layers: Vec<f32> = project.layered().iter().map(|d| d.layer).collect();
+        assert_eq!(layers, vec![1.0, 2.0]);
+        assert!(Project::default().bounds().is_none());
+    }
 }
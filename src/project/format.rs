@@ -0,0 +1,409 @@
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{Context, Result, bail};
+use bevy::prelude::default;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use super::{Drawing, Project};
+
+/// Magic bytes identifying a versioned binary project file
+const MAGIC: &[u8; 4] = b"TERP";
+/// Current project format version.
+/// Bump this and add a migration to `migrate` whenever the serialized `Project` changes.
+pub(crate) const VERSION: u16 = 2;
+const HEADER_LEN: usize = MAGIC.len() + size_of::<u16>();
+
+/// Serialization format of a project file
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Versioned flexbuffers
+    #[default]
+    Binary,
+    /// Human readable RON (`.ron`, e.g. `project.terp.ron`)
+    Ron,
+    /// Human readable JSON (`.terp.json`, any other `.json` is a Lottie animation)
+    Json,
+}
+
+impl Format {
+    /// Format to save to, chosen by file extension
+    pub(crate) fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("ron") => Self::Ron,
+            Some("json") if is_project_json(path) => Self::Json,
+            _ => Self::Binary,
+        }
+    }
+
+    /// Format to save a project to `path`, refusing names kept for Lottie animations
+    pub(crate) fn for_save(path: &Path) -> Result<Self> {
+        if path.extension().and_then(OsStr::to_str) == Some("json") && !is_project_json(path) {
+            bail!(
+                "{} is a Lottie animation name, save projects as .terp.json",
+                path.display()
+            );
+        }
+        Ok(Self::from_path(path))
+    }
+
+    // Sniff loaded data, it may not have come from a file (e.g. web)
+    fn detect(data: &[u8]) -> Self {
+        match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => Self::Json,
+            Some(b'(') => Self::Ron,
+            _ => Self::Binary,
+        }
+    }
+}
+
+// Only names ending in .terp.json are JSON projects
+fn is_project_json(path: &Path) -> bool {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| name.ends_with(".terp.json"))
+}
+
+// Text formats wrap the project with its version
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u16,
+    project: T,
+}
+
+#[derive(Deserialize)]
+struct EnvelopeVersion {
+    version: u16,
+}
+
+impl Project {
+    /// Decode project data in any format, upgrading older versions to the current `Project`
+    pub(crate) fn from_bytes(data: &[u8]) -> Result<Project> {
+        let project = match Format::detect(data) {
+            Format::Binary => {
+                let (version, payload) = split_header(data);
+                migrate(&BinaryDecoder { version, payload })?
+            }
+            format => {
+                let text = std::str::from_utf8(data).context("Invalid project text")?;
+                migrate(&TextDecoder::new(format, text)?)?
+            }
+        };
+        if let Some(problem) = project.settings.problems().first() {
+            bail!("Invalid project settings, {}", problem);
+        }
+        Ok(project)
+    }
+
+    /// Encode the project in `format`, including the current version
+    pub(crate) fn to_bytes(&self, format: Format) -> Result<Vec<u8>> {
+        let envelope = Envelope {
+            version: VERSION,
+            project: self,
+        };
+        Ok(match format {
+            Format::Binary => {
+                let mut serializer = flexbuffers::FlexbufferSerializer::new();
+                self.serialize(&mut serializer)?;
+                let payload = serializer.view();
+                let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+                data.extend_from_slice(MAGIC);
+                data.extend_from_slice(&VERSION.to_le_bytes());
+                data.extend_from_slice(payload);
+                data
+            }
+            Format::Ron => {
+                let config = ron::ser::PrettyConfig::new().indentor("  ".into());
+                let mut text = ron::ser::to_string_pretty(&envelope, config)?;
+                text.push('\n');
+                text.into_bytes()
+            }
+            Format::Json => {
+                let mut data = serde_json::to_vec_pretty(&envelope)?;
+                data.push(b'\n');
+                data
+            }
+        })
+    }
+}
+
+// Files written before versioning have no header, treat them as version 0
+fn split_header(data: &[u8]) -> (u16, &[u8]) {
+    match data.strip_prefix(MAGIC) {
+        Some(rest) if rest.len() >= size_of::<u16>() => {
+            let (version, payload) = rest.split_at(size_of::<u16>());
+            (u16::from_le_bytes([version[0], version[1]]), payload)
+        }
+        _ => (0, data),
+    }
+}
+
+/// Decodes a project version into the struct layout it was written with
+trait Decoder {
+    fn version(&self) -> u16;
+    fn decode<T: DeserializeOwned>(&self) -> Result<T>;
+}
+
+struct BinaryDecoder<'a> {
+    version: u16,
+    payload: &'a [u8],
+}
+
+impl Decoder for BinaryDecoder<'_> {
+    fn version(&self) -> u16 {
+        self.version
+    }
+
+    fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        let reader = flexbuffers::Reader::get_root(self.payload)
+            .with_context(|| format!("Invalid project data (version {})", self.version))?;
+        T::deserialize(reader)
+            .with_context(|| format!("Invalid project data (version {})", self.version))
+    }
+}
+
+struct TextDecoder<'a> {
+    version: u16,
+    format: Format,
+    text: &'a str,
+}
+
+impl<'a> TextDecoder<'a> {
+    fn new(format: Format, text: &'a str) -> Result<Self> {
+        let EnvelopeVersion { version } = parse_text(format, text)?;
+        Ok(Self {
+            version,
+            format,
+            text,
+        })
+    }
+}
+
+impl Decoder for TextDecoder<'_> {
+    fn version(&self) -> u16 {
+        self.version
+    }
+
+    fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        let envelope: Envelope<T> = parse_text(self.format, self.text)
+            .with_context(|| format!("Invalid project text (version {})", self.version))?;
+        Ok(envelope.project)
+    }
+}
+
+fn parse_text<T: DeserializeOwned>(format: Format, text: &str) -> Result<T> {
+    Ok(match format {
+        Format::Json => serde_json::from_str(text)?,
+        _ => ron::from_str(text)?,
+    })
+}
+
+/// Version 1 layout, before project settings
+#[derive(Deserialize)]
+struct ProjectV1 {
+    drawings: Vec<Drawing>,
+}
+
+impl From<ProjectV1> for Project {
+    // Older projects played with what are now the default settings,
+    // except they weren't cropped so the canvas is grown to fit every drawing
+    fn from(project: ProjectV1) -> Self {
+        let mut project = Self {
+            settings: default(),
+            drawings: project.drawings,
+        };
+        if let Some(bounds) = project
+            .bounds()
+            .filter(|b| b.min.is_finite() && b.max.is_finite())
+        {
+            // The canvas is centered on the origin
+            let size = bounds.min.abs().max(bounds.max.abs()) * 2.0;
+            project.settings.canvas = project.settings.canvas.max(size.ceil());
+        }
+        project
+    }
+}
+
+// Each version decodes into the struct layout it was written with,
+// then is converted forward one version at a time until it is the current `Project`.
+fn migrate(decoder: &impl Decoder) -> Result<Project> {
+    match decoder.version() {
+        // Version 0 (unversioned) has the same layout as version 1
+        0 | 1 => decoder.decode::<ProjectV1>().map(Project::from),
+        2 => decoder.decode::<Project>(),
+        version => bail!(
+            "Unsupported project version {} (newest supported version is {})",
+            version,
+            VERSION
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        points::{Points, PointsSettings},
+        project::ProjectSettings,
+    };
+    use bevy::prelude::*;
+
+    fn project() -> Project {
+        Project {
+            drawings: vec![Drawing {
+                source_settings: PointsSettings {
+                    color: LinearRgba::RED,
+                    radius: 5.0,
+                },
+                target_settings: PointsSettings {
+                    color: LinearRgba::BLUE,
+                    radius: 10.0,
+                },
+                source_points: Points(vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 2.0)]),
+                target_points: Points(vec![Vec2::new(3.0, 4.0), Vec2::new(5.0, 6.0)]),
+                layer: 1.0,
+            }],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = project().to_bytes(Format::Binary).unwrap();
+        assert!(data.starts_with(MAGIC));
+        assert_eq!(split_header(&data).0, VERSION);
+
+        let project = Project::from_bytes(&data).unwrap();
+        assert_eq!(project.drawings.len(), 1);
+        let drawing = &project.drawings[0];
+        assert_eq!(drawing.source_settings.radius, 5.0);
+        assert_eq!(drawing.target_settings.color, LinearRgba::BLUE);
+        assert_eq!(drawing.target_points.0[1], Vec2::new(5.0, 6.0));
+        assert_eq!(drawing.layer, 1.0);
+    }
+
+    #[test]
+    fn test_unversioned() {
+        let mut serializer = flexbuffers::FlexbufferSerializer::new();
+        project().serialize(&mut serializer).unwrap();
+        let project = Project::from_bytes(serializer.view()).unwrap();
+        assert_eq!(project.drawings[0].source_points.0.len(), 2);
+
+        let demo = Project::from_bytes(include_bytes!("../../web/demo/smile.terp")).unwrap();
+        assert!(!demo.drawings.is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut data = project().to_bytes(Format::Binary).unwrap();
+        data[MAGIC.len()..HEADER_LEN].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let err = Project::from_bytes(&data).err().unwrap();
+        assert!(err.to_string().contains("Unsupported project version"));
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut project = project();
+        let drawing = &mut project.drawings[0];
+        drawing.source_points.0.push(Vec2::new(0.1, -1.0e-7));
+        drawing.target_points.0.push(Vec2::new(f32::MAX, 1.0 / 3.0));
+        let binary = project.to_bytes(Format::Binary).unwrap();
+
+        for format in [Format::Ron, Format::Json] {
+            let text = project.to_bytes(format).unwrap();
+            assert_eq!(Format::detect(&text), format);
+            let decoded = Project::from_bytes(&text).unwrap();
+            assert_eq!(decoded.to_bytes(Format::Binary).unwrap(), binary);
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("a.terp")), Format::Binary);
+        assert_eq!(Format::from_path(Path::new("a.terp.ron")), Format::Ron);
+        assert_eq!(Format::from_path(Path::new("a.terp.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("a.json")), Format::Binary);
+        assert_eq!(Format::from_path(Path::new("a")), Format::Binary);
+
+        assert_eq!(
+            Format::for_save(Path::new("a.terp.json")).unwrap(),
+            Format::Json
+        );
+        assert!(Format::for_save(Path::new("a.json")).is_err());
+    }
+
+    #[test]
+    fn test_text_unsupported_version() {
+        let text = format!("(version: {}, project: (drawings: []))", VERSION + 1);
+        assert!(Project::from_bytes(text.as_bytes()).is_err());
+        let project =
+            Project::from_bytes(b"{\"version\": 1, \"project\": {\"drawings\": []}}").unwrap();
+        assert!(project.drawings.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_settings() {
+        let v1 = b"{\"version\": 1, \"project\": {\"drawings\": []}}";
+        let project = Project::from_bytes(v1).unwrap();
+        assert_eq!(project.settings, ProjectSettings::default());
+
+        // Drawings beyond the default canvas stay visible
+        let mut wide = self::project();
+        wide.drawings[0].target_points.0[0] = Vec2::new(-500.0, 100.0);
+        let v1 = format!(
+            "{{\"version\": 1, \"project\": {{\"drawings\": {}}}}}",
+            serde_json::to_string(&wide.drawings).unwrap()
+        );
+        let project = Project::from_bytes(v1.as_bytes()).unwrap();
+        assert_eq!(project.settings.canvas, Vec2::new(1020.0, 600.0));
+        let canvas = project.settings.canvas_rect();
+        let bounds = project.bounds().unwrap();
+        assert!(canvas.contains(bounds.min) && canvas.contains(bounds.max));
+
+        let mut project = self::project();
+        project.settings = ProjectSettings {
+            canvas: Vec2::new(800.0, 400.0),
+            background: LinearRgba::WHITE,
+            duration: 1.5,
+            easing: EaseFunction::Steps(4),
+        };
+        for format in [Format::Binary, Format::Ron, Format::Json] {
+            let data = project.to_bytes(format).unwrap();
+            assert_eq!(
+                Project::from_bytes(&data).unwrap().settings,
+                project.settings
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(Project::from_bytes(b"TERP\x01\x00garbage").is_err());
+        assert!(Project::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        for settings in [
+            ProjectSettings {
+                duration: 0.0,
+                ..default()
+            },
+            ProjectSettings {
+                duration: f32::INFINITY,
+                ..default()
+            },
+            ProjectSettings {
+                canvas: Vec2::new(600.0, -1.0),
+                ..default()
+            },
+        ] {
+            let mut project = project();
+            project.settings = settings;
+            for format in [Format::Binary, Format::Ron] {
+                let data = project.to_bytes(format).unwrap();
+                let err = Project::from_bytes(&data).err().unwrap();
+                assert!(err.to_string().contains("Invalid project settings"));
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Result};
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+};
+
+use super::{ProjectSaved, SaveProjectData};
+
+/// Previous versions kept when a project file is overwritten
+pub(crate) const BACKUPS: usize = 3;
+
+/// Saves waiting to be written, one at a time in order so backups rotate correctly
+#[derive(Resource, Default)]
+pub(super) struct Writer {
+    queue: VecDeque<SaveProjectData>,
+    /// Gives back the save once it is written
+    task: Option<Task<Result<SaveProjectData>>>,
+}
+
+/// Write `data` to `path` without ever leaving a partially written file.
+/// The data is written to a temporary file alongside and renamed over `path`,
+/// after the existing file is kept as `path.1.bak` (the previous `backups` versions are kept,
+/// `.1.bak` is the newest).
+pub(crate) fn write(path: &Path, data: &[u8], backups: usize) -> Result<()> {
+    let temp = sibling(path, &format!(".{}.tmp", process::id()));
+    let result = write_temp(&temp, data)
+        .and_then(|_| rotate_backups(path, backups))
+        .and_then(|_| {
+            fs::rename(&temp, path).with_context(|| format!("Failed to replace {}", path.display()))
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Backup `index` of `path`, 1 is the newest
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    sibling(path, &format!(".{}.bak", index))
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn write_temp(temp: &Path, data: &[u8]) -> Result<()> {
+    let mut file =
+        File::create(temp).with_context(|| format!("Failed to create {}", temp.display()))?;
+    file.write_all(data)?;
+    // Make sure the data is on disk before it replaces the project
+    file.sync_all()?;
+    Ok(())
+}
+
+fn rotate_backups(path: &Path, backups: usize) -> Result<()> {
+    if backups == 0 || !path.exists() {
+        return Ok(());
+    }
+    for index in (1..backups).rev() {
+        let backup = backup_path(path, index);
+        if backup.exists() {
+            fs::rename(&backup, backup_path(path, index + 1))?;
+        }
+    }
+    // Link rather than move, so `path` exists until it is replaced
+    let newest = backup_path(path, 1);
+    if newest.exists() {
+        fs::remove_file(&newest)?;
+    }
+    fs::hard_link(path, &newest)
+        .or_else(|_| fs::copy(path, &newest).map(|_| ()))
+        .with_context(|| format!("Failed to back up {}", path.display()))
+}
+
+pub(super) fn save_project_data(
+    mut events: EventReader<SaveProjectData>,
+    mut writer: ResMut<Writer>,
+    mut saved: EventWriter<ProjectSaved>,
+) -> Result<()> {
+    for event in events.read() {
+        // Only the latest data for a path needs writing
+        writer.queue.retain(|queued| queued.path != event.path);
+        writer.queue.push_back(SaveProjectData {
+            path: event.path.clone(),
+            data: event.data.clone(),
+            backups: event.backups,
+        });
+    }
+
+    if let Some(task) = writer.task.as_mut() {
+        let Some(result) = block_on(future::poll_once(task)) else {
+            return Ok(());
+        };
+        writer.task = None;
+        saved.send(ProjectSaved(result?));
+    }
+    if let Some(save) = writer.queue.pop_front() {
+        writer.task = Some(
+            IoTaskPool::get()
+                .spawn(async move { write(&save.path, &save.data, save.backups).map(|_| save) }),
+        );
+    }
+    Ok(())
+}
+
+/// Finish every save before the app exits, so quitting straight after saving keeps the save
+pub(super) fn flush_on_exit(mut writer: ResMut<Writer>) -> Result<()> {
+    let task = writer.task.take();
+    let queue = std::mem::take(&mut writer.queue);
+    // Keep writing after a failure, only the first error is reported
+    let mut result = task.map_or(Ok(()), |task| block_on(task).map(drop));
+    for save in queue {
+        result = result.and(write(&save.path, &save.data, save.backups));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("terp-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_write() {
+        let directory = test_directory("write");
+        let path = directory.join("project.terp");
+        for version in 0..5u8 {
+            write(&path, &[version], 2).unwrap();
+        }
+        assert_eq!(fs::read(&path).unwrap(), [4]);
+        assert_eq!(fs::read(backup_path(&path, 1)).unwrap(), [3]);
+        assert_eq!(fs::read(backup_path(&path, 2)).unwrap(), [2]);
+        assert!(!backup_path(&path, 3).exists());
+        // No temporary files left behind
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 3);
+
+        let recovery = directory.join("recovery.terp");
+        write(&recovery, &[0], 0).unwrap();
+        write(&recovery, &[1], 0).unwrap();
+        assert_eq!(fs::read(&recovery).unwrap(), [1]);
+        assert!(!backup_path(&recovery, 1).exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_backup_path() {
+        assert_eq!(
+            backup_path(Path::new("dir/project.terp.ron"), 2),
+            Path::new("dir/project.terp.ron.2.bak")
+        );
+    }
+
+    #[test]
+    fn test_save_project_data() {
+        let directory = test_directory("save");
+        let path = directory.join("project.terp");
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Writer>()
+            .add_event::<SaveProjectData>()
+            .add_event::<ProjectSaved>()
+            .init_resource::<Saved>()
+            .add_systems(
+                Update,
+                (
+                    save_project_data.pipe(crate::error_handler),
+                    |mut events: EventReader<ProjectSaved>, mut saved: ResMut<Saved>| {
+                        saved
+                            .0
+                            .extend(events.read().map(|event| event.0.path.clone()));
+                    },
+                )
+                    .chain(),
+            );
+        for version in 0..3u8 {
+            app.world_mut().send_event(SaveProjectData {
+                path: path.clone(),
+                data: vec![version],
+                backups: BACKUPS,
+            });
+        }
+        // Failed writes are not reported as saved
+        let missing = directory.join("missing").join("project.terp");
+        app.world_mut().send_event(SaveProjectData {
+            path: missing.clone(),
+            data: vec![0],
+            backups: 0,
+        });
+
+        for _ in 0..1000 {
+            app.update();
+            let writer = app.world().resource::<Writer>();
+            if writer.task.is_none() && writer.queue.is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        // Queued saves of the same path are coalesced
+        assert_eq!(fs::read(&path).unwrap(), [2]);
+        assert!(!backup_path(&path, 1).exists());
+        assert!(!missing.exists());
+        assert_eq!(app.world().resource::<Saved>().0, vec![path]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[derive(Resource, Default)]
+    struct Saved(Vec<PathBuf>);
+
+    #[test]
+    fn test_flush_on_exit() {
+        let directory = test_directory("exit");
+        let paths = [directory.join("a.terp"), directory.join("b.terp")];
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Writer>()
+            .add_event::<SaveProjectData>()
+            .add_event::<ProjectSaved>()
+            .add_event::<AppExit>()
+            .add_systems(Update, save_project_data.pipe(crate::error_handler))
+            .add_systems(
+                Last,
+                flush_on_exit
+                    .pipe(crate::error_handler)
+                    .run_if(on_event::<AppExit>),
+            );
+        for path in &paths {
+            app.world_mut().send_event(SaveProjectData {
+                path: path.clone(),
+                data: vec![1],
+                backups: 0,
+            });
+        }
+        // One save is in flight and the other queued when the app exits
+        app.world_mut().send_event(AppExit::Success);
+        app.update();
+
+        let writer = app.world().resource::<Writer>();
+        assert!(writer.task.is_none() && writer.queue.is_empty());
+        for path in &paths {
+            assert_eq!(fs::read(path).unwrap(), [1]);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
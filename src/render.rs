@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use image::{Rgba, RgbaImage};
+
+use crate::project::Project;
+
+/// Standard 4x MSAA sample positions, cameras use 4x MSAA by default
+const SAMPLES: [Vec2; 4] = [
+    Vec2::new(0.375, 0.125),
+    Vec2::new(0.875, 0.375),
+    Vec2::new(0.125, 0.625),
+    Vec2::new(0.625, 0.875),
+];
+
+/// Software rasterizer that renders a project the same way `PointsMaterial` does, without a GPU.
+/// Each point is an opaque disc of the interpolated radius and color,
+/// drawings are layered by `Drawing::layer` and colors are mixed in linear space.
+pub(crate) struct Renderer {
+    size: UVec2,
+}
+
+impl Renderer {
+    /// Render `size` pixel images, with the project canvas scaled to fit as the player does
+    pub(crate) fn new(size: UVec2) -> Self {
+        Self { size }
+    }
+
+    /// Render the project at interpolation `t`
+    pub(crate) fn render(&self, project: &Project, t: f32) -> RgbaImage {
+        let size = self.size.as_vec2();
+        let canvas = project.settings.canvas_rect();
+        // World units per pixel, fitting the canvas in the image
+        let scale = (canvas.size() / size).max_element();
+        let to_pixels = |point: Vec2| {
+            let offset = (point - canvas.center()) / scale;
+            // Pixels are y down
+            size / 2.0 + Vec2::new(offset.x, -offset.y)
+        };
+
+        let width = self.size.x as usize;
+        let mut samples =
+            vec![project.settings.background; width * self.size.y as usize * SAMPLES.len()];
+        for drawing in project.layered() {
+            let (points, settings) = drawing.interpolate(t);
+            let radius = settings.radius / scale;
+            for point in points.0.iter().map(|point| to_pixels(*point)) {
+                let min = (point - radius).floor().max(Vec2::ZERO).as_uvec2();
+                let max = (point + radius).ceil().min(size).as_uvec2();
+                for y in min.y..max.y {
+                    for x in min.x..max.x {
+                        let pixel = Vec2::new(x as f32, y as f32);
+                        let index = (y as usize * width + x as usize) * SAMPLES.len();
+                        for (sample, offset) in SAMPLES.iter().enumerate() {
+                            if (pixel + *offset).distance_squared(point) <= radius * radius {
+                                samples[index + sample] = settings.color;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Resolve by averaging samples in linear space
+        RgbaImage::from_fn(self.size.x, self.size.y, |x, y| {
+            let index = (y as usize * width + x as usize) * SAMPLES.len();
+            let color = samples[index..index + SAMPLES.len()]
+                .iter()
+                .fold(LinearRgba::NONE, |sum, sample| sum + *sample)
+                / SAMPLES.len() as f32;
+            Rgba(Srgba::from(color).with_alpha(1.0).to_u8_array())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        points::{Points, PointsSettings},
+        project::{Drawing, ProjectSettings},
+    };
+    use std::path::Path;
+
+    fn drawing(layer: f32, color: LinearRgba, points: Vec<Vec2>) -> Drawing {
+        let settings = PointsSettings {
+            color,
+            radius: 10.0,
+        };
+        Drawing {
+            source_settings: settings,
+            target_settings: PointsSettings {
+                radius: 20.0,
+                ..settings
+            },
+            source_points: Points(points.clone()),
+            target_points: Points(points.iter().map(|p| *p + Vec2::X * 100.0).collect()),
+            layer,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let mut project = Project {
+            drawings: vec![
+                drawing(2.0, LinearRgba::RED, vec![Vec2::ZERO]),
+                drawing(1.0, LinearRgba::BLUE, vec![Vec2::new(5.0, 0.0)]),
+            ],
+            ..default()
+        };
+        let renderer = Renderer::new(UVec2::splat(600));
+        let background = Rgba([43, 44, 47, 255]);
+
+        let image = renderer.render(&project, 0.0);
+        // Red is layered above blue
+        assert_eq!(*image.get_pixel(300, 300), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(313, 300), Rgba([0, 0, 255, 255]));
+        assert_eq!(*image.get_pixel(300, 315), background);
+        // Edges are antialiased
+        let edge = image.get_pixel(293, 307);
+        assert_ne!(*edge, background);
+        assert_ne!(*edge, Rgba([255, 0, 0, 255]));
+
+        // Halfway the points have moved 50 and grown to radius 15
+        let image = renderer.render(&project, 0.5);
+        assert_eq!(*image.get_pixel(350, 314), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(350, 316), background);
+        assert_eq!(*image.get_pixel(300, 300), background);
+
+        // Scaled to fit a smaller image
+        let image = Renderer::new(UVec2::new(300, 150)).render(&project, 0.0);
+        assert_eq!(*image.get_pixel(150, 75), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(150, 79), background);
+
+        // A smaller canvas is scaled up, over the project background
+        project.settings = ProjectSettings {
+            canvas: Vec2::splat(300.0),
+            background: LinearRgba::WHITE,
+            ..default()
+        };
+        let image = renderer.render(&project, 0.0);
+        assert_eq!(*image.get_pixel(300, 318), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(300, 322), Rgba([255, 255, 255, 255]));
+    }
+
+    // Set TERP_BLESS=1 to regenerate golden images after intentional rendering changes
+    #[test]
+    fn test_golden_demos() {
+        let renderer = Renderer::new(UVec2::splat(150));
+        for name in ["smile", "sunrise", "x"] {
+            let project = Project::load(Path::new(&format!("web/demo/{}.terp", name))).unwrap();
+            let image = renderer.render(&project, 0.5);
+            let golden = format!("src/render/golden/{}.png", name);
+            if std::env::var_os("TERP_BLESS").is_some() {
+                image.save(&golden).unwrap();
+                continue;
+            }
+            let expected = image::open(&golden).unwrap().into_rgba8();
+            assert_eq!(image.dimensions(), expected.dimensions());
+            // Allow for floating point differences between platforms
+            let differences = image
+                .pixels()
+                .zip(expected.pixels())
+                .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 1))
+                .count();
+            assert!(
+                differences < 20,
+                "{} differs in {} pixels",
+                name,
+                differences
+            );
+        }
+    }
+}